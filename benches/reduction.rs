@@ -0,0 +1,62 @@
+// Benchmarks für den heißen Pfad im Audio-Callback: die Reduktion eines Chunks auf kleinsten und
+// größten Wert je Kanal und die ganze Verarbeitung eines Puffers. Die Puffergrößen decken kleine
+// Treiberpuffer bis zu großen Chunks ab, die Kanalzahlen Mono, Stereo und ein 8-Kanal-Interface.
+//
+// Ausführen mit `cargo bench --bench reduction`, den SIMD-Pfad mit `--features simd` dazu. Ohne
+// das Feature messen `reduce_chunk` und `reduce_chunk_scalar` denselben Code.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rmnc::{process_audio, reduce_chunk, reduce_chunk_scalar, ProcessState, WaveformMode};
+
+/// Frames je Puffer bzw. Chunk
+const FRAMES: [usize; 3] = [256, 1024, 4096];
+
+const CHANNELS: [usize; 3] = [1, 2, 8];
+
+/// Deterministisches Testsignal, je Kanal eine andere Frequenz, damit kein Kanal konstant ist.
+fn signal(frames: usize, channels: usize) -> Vec<f32> {
+    (0..frames * channels).map(|idx| ((idx / channels) as f32 * 0.01 * (1 + idx % channels) as f32).sin()).collect()
+}
+
+fn reduce_chunk_bench(c: &mut Criterion) {
+    for mode in [WaveformMode::Peak, WaveformMode::Rms] {
+        let mut group = c.benchmark_group(format!("reduce_chunk/{:?}", mode));
+        for channels in CHANNELS {
+            for frames in FRAMES {
+                let chunk = signal(frames, channels);
+                group.throughput(Throughput::Elements(chunk.len() as u64));
+                group.bench_with_input(BenchmarkId::new(format!("{}ch", channels), frames), &chunk, |b, chunk| {
+                    b.iter(|| reduce_chunk(black_box(chunk), channels, mode, false));
+                });
+                group.bench_with_input(BenchmarkId::new(format!("{}ch/scalar", channels), frames), &chunk, |b, chunk| {
+                    b.iter(|| reduce_chunk_scalar(black_box(chunk), channels, mode, false));
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+fn process_audio_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("process_audio");
+    for channels in CHANNELS {
+        for frames in FRAMES {
+            let buffer = signal(frames, channels);
+            // Ein Punkt je 1024 Frames, wie bei Stereo mit der Standard-Chunk-Größe
+            let chunk_size = 1024 * channels;
+            let mut state = ProcessState::default();
+            group.throughput(Throughput::Elements(buffer.len() as u64));
+            group.bench_with_input(BenchmarkId::new(format!("{}ch", channels), frames), &buffer, |b, buffer| {
+                b.iter(|| process_audio(black_box(buffer.as_slice()), channels, chunk_size, WaveformMode::Peak, false, 0, None, None, None, None, &mut state, |point| {
+                    black_box(point);
+                }));
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, reduce_chunk_bench, process_audio_bench);
+criterion_main!(benches);
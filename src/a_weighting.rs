@@ -0,0 +1,99 @@
+// A-Bewertung nach IEC 61672 als Kaskade von drei Biquads, per bilinearer Transformation aus dem
+// analogen Filter gewonnen. Der Zustand bleibt wie beim DC-Blocker zwischen den Aufrufen erhalten.
+
+use std::f64::consts::PI;
+
+use rustfft::num_complex::Complex;
+
+use crate::biquad::Biquad;
+
+/// Pol-Frequenzen des analogen Filters in Hz
+const POLE_1: f64 = 20.598_997;
+const POLE_2: f64 = 107.652_65;
+const POLE_3: f64 = 737.862_23;
+const POLE_4: f64 = 12_194.217;
+
+/// Frequenz, bei der die Bewertung 0 dB beträgt
+const REFERENCE_HZ: f64 = 1000.0;
+
+/// A-Bewertungsfilter je Kanal
+#[derive(Default)]
+pub struct AWeighting {
+    sections: [Biquad; 3],
+    /// Verstärkung, damit die Bewertung bei 1 kHz 0 dB beträgt
+    gain: f32,
+    /// Abtastrate, für die `sections` berechnet sind, 0 = noch nicht berechnet
+    sample_rate: u32,
+    /// Zustand der drei Biquads je Kanal
+    state: Vec<[[f32; 2]; 3]>,
+}
+
+impl AWeighting {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn design(&mut self, sample_rate: u32) {
+        let fs = sample_rate.max(1) as f64;
+        let w = |hz: f64| 2.0 * PI * hz;
+        self.sections = [
+            // s² / (s + ω1)²
+            Biquad::bilinear([0.0, 0.0, 1.0], [w(POLE_1) * w(POLE_1), 2.0 * w(POLE_1), 1.0], fs),
+            // s² / ((s + ω2)(s + ω3))
+            Biquad::bilinear([0.0, 0.0, 1.0], [w(POLE_2) * w(POLE_3), w(POLE_2) + w(POLE_3), 1.0], fs),
+            // 1 / (s + ω4)²
+            Biquad::bilinear([1.0, 0.0, 0.0], [w(POLE_4) * w(POLE_4), 2.0 * w(POLE_4), 1.0], fs),
+        ];
+        let response: Complex<f64> = self.sections.iter().map(|section| section.response(REFERENCE_HZ, fs)).product();
+        self.gain = (1.0 / response.norm()) as f32;
+        self.sample_rate = sample_rate;
+        self.reset();
+    }
+
+    /// Filtert interleavte Samples an Ort und Stelle, `samples[0]` gehört zu `first_channel`.
+    /// Ändern sich Kanalzahl oder Abtastrate, beginnt das Filter von vorn.
+    pub fn process(&mut self, samples: &mut [f32], channels: usize, first_channel: usize, sample_rate: u32) {
+        let channels = channels.max(1);
+        if self.sample_rate != sample_rate {
+            self.design(sample_rate);
+        }
+        if self.state.len() != channels {
+            self.state.clear();
+            self.state.resize(channels, [[0.0; 2]; 3]);
+        }
+        for (idx, sample) in samples.iter_mut().enumerate() {
+            let state = &mut self.state[(first_channel + idx) % channels];
+            let mut value = *sample * self.gain;
+            for (section, section_state) in self.sections.iter().zip(state.iter_mut()) {
+                value = section.process(value, section_state);
+            }
+            *sample = value;
+        }
+    }
+
+    /// Verwirft den Zustand aller Kanäle.
+    pub fn reset(&mut self) {
+        self.state.fill([[0.0; 2]; 3]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_weighting_matches_reference_gains() {
+        // Pegel eines Sinus nach dem Filter relativ zum Eingang, nach dem Einschwingen
+        let gain_db = |hz: f32| {
+            let input: Vec<f32> = (0..48000).map(|i| (2.0 * std::f32::consts::PI * hz * i as f32 / 48000.0).sin()).collect();
+            let mut output = input.clone();
+            AWeighting::new().process(&mut output, 1, 0, 48000);
+            let rms = |samples: &[f32]| (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+            20.0 * (rms(&output[24000..]) / rms(&input[24000..])).log10()
+        };
+        assert!(gain_db(1000.0).abs() < 0.1, "{}", gain_db(1000.0));
+        // Tabellenwerte aus IEC 61672: -19,1 dB bei 100 Hz, +1,3 dB bei 2,5 kHz
+        assert!((gain_db(100.0) + 19.1).abs() < 0.3, "{}", gain_db(100.0));
+        assert!((gain_db(2500.0) - 1.3).abs() < 0.3, "{}", gain_db(2500.0));
+    }
+}
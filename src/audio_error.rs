@@ -0,0 +1,100 @@
+// Fehler beim Öffnen des Eingabe-Streams. Die Varianten unterscheiden, was der Benutzer dagegen
+// tun kann, damit die UI einen passenden Hinweis zeigt.
+
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum AudioError {
+    /// Kein Eingabegerät vorhanden
+    NoDevice,
+    /// Kein Gerät, über das sich die Wiedergabe aufnehmen lässt
+    NoLoopbackDevice,
+    /// Name des Geräts nicht lesbar, meist ist das Gerät gerade verschwunden
+    DeviceName(cpal::DeviceNameError),
+    /// Standardkonfiguration des Geräts nicht abfragbar
+    Config(cpal::DefaultStreamConfigError),
+    /// Sample-Format, für das es keinen Verarbeitungspfad gibt
+    UnsupportedFormat(cpal::SampleFormat),
+    /// Stream ließ sich nicht anlegen, z.B. weil das Gerät belegt ist
+    StreamBuild(cpal::BuildStreamError),
+    /// Stream ließ sich nicht starten
+    StreamPlay(cpal::PlayStreamError),
+}
+
+impl AudioError {
+    /// Was der Benutzer tun kann, um den Fehler zu beheben.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            AudioError::NoDevice => "Connect a microphone or audio interface, then press Retry.",
+            AudioError::NoLoopbackDevice => "This platform offers no loopback capture; disable Loopback or use a virtual audio device.",
+            AudioError::DeviceName(_) | AudioError::Config(_) => "The device may have been disconnected. Reconnect it, then press Retry.",
+            AudioError::UnsupportedFormat(_) => "Select a different device or change its format in the system audio settings.",
+            AudioError::StreamBuild(_) | AudioError::StreamPlay(_) => "Close other applications using the device, then press Retry.",
+        }
+    }
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioError::NoDevice => write!(f, "no input device available"),
+            AudioError::NoLoopbackDevice => write!(f, "no loopback device available"),
+            AudioError::DeviceName(err) => write!(f, "cannot read device name: {}", err),
+            AudioError::Config(err) => write!(f, "cannot retrieve default configuration: {}", err),
+            AudioError::UnsupportedFormat(format) => write!(f, "unsupported sample format {:?}", format),
+            AudioError::StreamBuild(err) => write!(f, "cannot open stream: {}", err),
+            AudioError::StreamPlay(err) => write!(f, "cannot start stream: {}", err),
+        }
+    }
+}
+
+impl Error for AudioError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AudioError::DeviceName(err) => Some(err),
+            AudioError::Config(err) => Some(err),
+            AudioError::StreamBuild(err) => Some(err),
+            AudioError::StreamPlay(err) => Some(err),
+            AudioError::NoDevice | AudioError::NoLoopbackDevice | AudioError::UnsupportedFormat(_) => None,
+        }
+    }
+}
+
+impl From<cpal::DeviceNameError> for AudioError {
+    fn from(err: cpal::DeviceNameError) -> Self {
+        AudioError::DeviceName(err)
+    }
+}
+
+impl From<cpal::DefaultStreamConfigError> for AudioError {
+    fn from(err: cpal::DefaultStreamConfigError) -> Self {
+        AudioError::Config(err)
+    }
+}
+
+impl From<cpal::BuildStreamError> for AudioError {
+    fn from(err: cpal::BuildStreamError) -> Self {
+        AudioError::StreamBuild(err)
+    }
+}
+
+impl From<cpal::PlayStreamError> for AudioError {
+    fn from(err: cpal::PlayStreamError) -> Self {
+        AudioError::StreamPlay(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audio_errors_keep_their_cause() {
+        let err: AudioError = cpal::BuildStreamError::DeviceNotAvailable.into();
+        assert!(matches!(err, AudioError::StreamBuild(_)));
+        assert!(err.source().is_some());
+        assert!(AudioError::NoDevice.source().is_none());
+        assert_ne!(err.hint(), AudioError::UnsupportedFormat(cpal::SampleFormat::U8).hint());
+    }
+}
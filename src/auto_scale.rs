@@ -0,0 +1,38 @@
+// Geglättete automatische Skalierung: die Verstärkung folgt dem sichtbaren Maximum mit getrennten
+// Zeitkonstanten für Attack (lauter werdendes Signal) und Release, statt in jedem Frame zu springen.
+
+use std::time::Duration;
+
+#[derive(Default)]
+pub struct AutoScale {
+    /// Aktuelle Verstärkung in dB, `None` bis zum ersten Frame
+    gain_db: Option<f32>,
+}
+
+impl AutoScale {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Führt die Verstärkung über die Dauer `elapsed` an `target` heran und liefert sie. Muss sie
+    /// sinken, weil das Signal lauter wurde, gilt `attack`, sonst `release`. Geglättet wird in dB
+    /// über die Zeit statt je Frame, damit das Gleiten nicht von der Bildrate abhängt.
+    pub fn update(&mut self, target: f32, elapsed: Duration, attack: Duration, release: Duration) -> f32 {
+        let target_db = 20.0 * target.max(f32::MIN_POSITIVE).log10();
+        let gain_db = match self.gain_db {
+            // Der erste Frame übernimmt das Ziel, sonst gleitet die Anzeige erst aus 0 dB heran
+            None => target_db,
+            Some(gain_db) => {
+                let time_constant = if target_db < gain_db { attack } else { release };
+                let weight = if time_constant.is_zero() { 1.0 } else { 1.0 - (-elapsed.as_secs_f32() / time_constant.as_secs_f32()).exp() };
+                gain_db + (target_db - gain_db) * weight
+            }
+        };
+        self.gain_db = Some(gain_db);
+        10f32.powf(gain_db / 20.0)
+    }
+
+    pub fn reset(&mut self) {
+        self.gain_db = None;
+    }
+}
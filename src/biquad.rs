@@ -0,0 +1,42 @@
+// Biquad-Filterstufe, gemeinsam für A-Bewertung und K-Bewertung.
+
+use std::f64::consts::PI;
+
+use rustfft::num_complex::Complex;
+
+/// Biquad in transponierter Direktform II, `a0` ist auf 1 normiert.
+#[derive(Clone, Copy, Default)]
+pub struct Biquad {
+    pub b: [f32; 3],
+    pub a: [f32; 2],
+}
+
+impl Biquad {
+    /// Stufe aus Koeffizienten, die durch `a0` geteilt werden.
+    pub fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        Biquad { b: [(b[0] / a[0]) as f32, (b[1] / a[0]) as f32, (b[2] / a[0]) as f32], a: [(a[1] / a[0]) as f32, (a[2] / a[0]) as f32] }
+    }
+
+    /// Bilineare Transformation von `(b2 s² + b1 s + b0) / (a2 s² + a1 s + a0)`.
+    pub fn bilinear(b: [f64; 3], a: [f64; 3], sample_rate: f64) -> Self {
+        let k = 2.0 * sample_rate;
+        let transform = |[c0, c1, c2]: [f64; 3]| [c2 * k * k + c1 * k + c0, 2.0 * (c0 - c2 * k * k), c2 * k * k - c1 * k + c0];
+        Biquad::new(transform(b), transform(a))
+    }
+
+    /// Frequenzgang bei `hz`.
+    pub fn response(&self, hz: f64, sample_rate: f64) -> Complex<f64> {
+        let z1 = Complex::from_polar(1.0, -2.0 * PI * hz / sample_rate);
+        let z2 = z1 * z1;
+        let numerator = self.b[0] as f64 + z1 * self.b[1] as f64 + z2 * self.b[2] as f64;
+        let denominator = 1.0 + z1 * self.a[0] as f64 + z2 * self.a[1] as f64;
+        numerator / denominator
+    }
+
+    pub fn process(&self, x: f32, state: &mut [f32; 2]) -> f32 {
+        let y = self.b[0] * x + state[0];
+        state[0] = self.b[1] * x - self.a[0] * y + state[1];
+        state[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
@@ -0,0 +1,79 @@
+// Einmessen der festen Verstärkung für eine neue Quelle: Über eine kurze Messzeit wird der
+// größte Pegel der angezeigten Kanäle gehalten, danach wird die Verstärkung so gesetzt, dass
+// dieser Spitzenwert um `REFERENCE_DB` unter dem oberen Rand der Spur liegt.
+
+use std::time::Duration;
+
+use crate::render_plot::{AmplitudeScale, LANE_FULL_SCALE};
+
+/// Messzeit
+pub const DURATION: Duration = Duration::from_secs(2);
+
+/// Abstand des gemessenen Spitzenwerts zum oberen Rand der Spur
+pub const REFERENCE_DB: f32 = -6.0;
+
+pub struct Calibration {
+    remaining: Duration,
+    /// Größter bisher gemessener Pegel (±1.0 = 0 dBFS)
+    peak: f32,
+}
+
+impl Calibration {
+    pub fn new() -> Self {
+        Calibration { remaining: DURATION, peak: 0.0 }
+    }
+
+    /// Übernimmt den größten Pegel `level` der Punkte, die in den letzten `elapsed` angekommen
+    /// sind. Liefert den Spitzenwert der ganzen Messung, sobald die Messzeit um ist.
+    pub fn update(&mut self, level: f32, elapsed: Duration) -> Option<f32> {
+        self.peak = self.peak.max(level);
+        self.remaining = self.remaining.saturating_sub(elapsed);
+        self.remaining.is_zero().then_some(self.peak)
+    }
+
+    /// Restliche Messzeit
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+}
+
+/// Verstärkung in dB, mit der `peak` um `reference_db` unter dem oberen Rand der Spur liegt.
+/// In der linearen Skala füllt `LANE_FULL_SCALE` die Spur, in der dB-Skala 0 dBFS. Ohne Signal
+/// gibt es nichts einzumessen.
+pub fn gain_db(peak: f32, reference_db: f32, scale: AmplitudeScale) -> Option<f32> {
+    if peak <= 0.0 || !peak.is_finite() {
+        return None;
+    }
+    let full_scale = match scale {
+        AmplitudeScale::Linear => LANE_FULL_SCALE,
+        AmplitudeScale::Db { .. } => 1.0,
+    };
+    Some(20.0 * (full_scale / peak).log10() + reference_db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calibration_maps_the_measured_peak_to_the_reference() {
+        let mut calibration = Calibration::new();
+        let frame = Duration::from_millis(500);
+        assert_eq!(calibration.update(0.25, frame), None);
+        assert_eq!(calibration.update(0.5, frame), None);
+        assert_eq!(calibration.remaining(), Duration::from_secs(1));
+        assert_eq!(calibration.update(0.1, frame), None);
+        // Der Spitzenwert der ganzen Messung, nicht der letzte Pegel
+        assert_eq!(calibration.update(0.1, frame), Some(0.5));
+
+        // -6 dBFS soll 6 dB unter 0 dBFS liegen: keine Verstärkung
+        let gain = gain_db(0.5, -6.0, AmplitudeScale::Db { floor_db: -60.0 }).unwrap();
+        assert!(gain.abs() < 0.1, "{}", gain);
+        // Linear füllt erst LANE_FULL_SCALE die Spur, also 6 dB mehr
+        let gain = gain_db(0.5, -6.0, AmplitudeScale::Linear).unwrap();
+        assert!((gain - 6.0).abs() < 0.1, "{}", gain);
+        let gain = gain_db(0.05, -6.0, AmplitudeScale::Db { floor_db: -60.0 }).unwrap();
+        assert!((gain - 20.0).abs() < 0.1, "{}", gain);
+        assert_eq!(gain_db(0.0, -6.0, AmplitudeScale::Linear), None);
+    }
+}
@@ -0,0 +1,75 @@
+// Auslöser für die automatische Aufnahme kurzer Clips im unbeaufsichtigten Betrieb. Ein lautes
+// Ereignis soll genau einen Clip ergeben: Nach dem Auslösen muss der Clip fertig geschrieben sein
+// und der Pegel eine Weile unter der Schwelle bleiben, bevor der nächste Clip beginnen kann.
+
+use std::time::Duration;
+
+/// So lange muss der Pegel nach einem Clip unter der Schwelle bleiben
+pub const HOLDOFF: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+pub struct ClipTrigger {
+    /// Seit dem letzten Clip war der Pegel noch nicht lange genug leise
+    fired: bool,
+    /// Dauer, die der Pegel nach dem Clip bereits unter der Schwelle liegt
+    quiet_for: Duration,
+}
+
+impl ClipTrigger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Übernimmt den größten Wert `level` (±1.0 = 0 dBFS) der Punkte, die in den letzten
+    /// `elapsed` angekommen sind. `busy` gibt an, ob der letzte Clip noch geschrieben wird.
+    /// Liefert, ob jetzt ein Clip beginnen soll.
+    pub fn update(&mut self, level: f32, threshold_db: f32, elapsed: Duration, busy: bool) -> bool {
+        let loud = 20.0 * level.max(f32::MIN_POSITIVE).log10() >= threshold_db;
+        if self.fired {
+            if loud || busy {
+                self.quiet_for = Duration::ZERO;
+            } else {
+                self.quiet_for += elapsed;
+                self.fired = self.quiet_for < HOLDOFF;
+            }
+            return false;
+        }
+        if loud {
+            self.fired = true;
+            self.quiet_for = Duration::ZERO;
+        }
+        loud
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_trigger_fires_once_per_event() {
+        let frame = Duration::from_millis(100);
+        let mut trigger = ClipTrigger::new();
+        assert!(!trigger.update(0.01, -20.0, frame, false));
+        assert!(trigger.update(0.5, -20.0, frame, false));
+        // Weiter laut oder noch beim Schreiben: kein zweiter Clip
+        assert!(!trigger.update(0.5, -20.0, frame, true));
+        for _ in 0..20 {
+            assert!(!trigger.update(0.01, -20.0, frame, true));
+        }
+        // Erst nach `HOLDOFF` Stille ohne laufenden Clip wieder scharf
+        let quiet_frames = HOLDOFF.as_millis() / frame.as_millis();
+        for _ in 0..quiet_frames - 1 {
+            assert!(!trigger.update(0.01, -20.0, frame, false));
+        }
+        assert!(!trigger.update(0.5, -20.0, frame, false));
+        for _ in 0..quiet_frames {
+            assert!(!trigger.update(0.01, -20.0, frame, false));
+        }
+        assert!(trigger.update(0.5, -20.0, frame, false));
+    }
+}
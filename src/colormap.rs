@@ -0,0 +1,119 @@
+// Farbskalen für Spektrogramm und Pegelbalken. Ein Wert von 0 bis 1 wird linear zwischen festen
+// Stützfarben interpoliert. Viridis, Magma und Cividis sind wahrnehmungsgleichförmig und bleiben
+// auch in Graustufen geordnet, Cividis ist zusätzlich für Rot-Grün-Schwäche ausgelegt.
+
+use image::{ImageBuffer, Rgba};
+use slint::{Image, Rgba8Pixel, SharedPixelBuffer};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Colormap {
+    #[default]
+    Viridis,
+    Magma,
+    Grayscale,
+    /// Farbenblind-sicher, von Dunkelblau nach Gelb
+    Cividis,
+}
+
+const VIRIDIS: [[f32; 3]; 9] = [
+    [68.0, 1.0, 84.0],
+    [71.0, 44.0, 122.0],
+    [59.0, 81.0, 139.0],
+    [44.0, 113.0, 142.0],
+    [33.0, 144.0, 141.0],
+    [39.0, 173.0, 129.0],
+    [92.0, 200.0, 99.0],
+    [170.0, 220.0, 50.0],
+    [253.0, 231.0, 37.0],
+];
+
+const MAGMA: [[f32; 3]; 9] = [
+    [0.0, 0.0, 4.0],
+    [28.0, 16.0, 68.0],
+    [79.0, 18.0, 123.0],
+    [129.0, 37.0, 129.0],
+    [181.0, 54.0, 122.0],
+    [229.0, 80.0, 100.0],
+    [251.0, 135.0, 97.0],
+    [254.0, 194.0, 135.0],
+    [252.0, 253.0, 191.0],
+];
+
+const GRAYSCALE: [[f32; 3]; 2] = [[0.0, 0.0, 0.0], [255.0, 255.0, 255.0]];
+
+const CIVIDIS: [[f32; 3]; 9] = [
+    [0.0, 34.0, 78.0],
+    [18.0, 53.0, 112.0],
+    [59.0, 73.0, 108.0],
+    [87.0, 92.0, 109.0],
+    [112.0, 113.0, 115.0],
+    [138.0, 134.0, 120.0],
+    [165.0, 156.0, 116.0],
+    [196.0, 180.0, 104.0],
+    [254.0, 232.0, 56.0],
+];
+
+/// Höhe des Farbstreifens für die Pegelbalken in Pixeln, Slint streckt ihn auf die Balkenhöhe
+const STRIP_HEIGHT: u32 = 64;
+
+impl Colormap {
+    /// Farbskala für `colormap` in der UI, unbekannte Werte ergeben Viridis.
+    pub fn from_index(index: i32) -> Self {
+        match index {
+            1 => Colormap::Magma,
+            2 => Colormap::Grayscale,
+            3 => Colormap::Cividis,
+            _ => Colormap::Viridis,
+        }
+    }
+
+    /// Farbe für `value` von 0 (dunkel) bis 1 (hell), Werte außerhalb werden begrenzt.
+    pub fn map(self, value: f32) -> Rgba<u8> {
+        let stops = self.stops();
+        let value = if value.is_nan() { 0.0 } else { value.clamp(0.0, 1.0) };
+        let position = value * (stops.len() - 1) as f32;
+        let index = (position as usize).min(stops.len() - 2);
+        let t = position - index as f32;
+        let channel = |c: usize| (stops[index][c] + (stops[index + 1][c] - stops[index][c]) * t).round() as u8;
+        Rgba([channel(0), channel(1), channel(2), 255])
+    }
+
+    /// Senkrechter Streifen über die ganze Skala, 0 unten und 1 oben, eine Spalte breit.
+    pub fn strip(self) -> Image {
+        let img = ImageBuffer::from_fn(1, STRIP_HEIGHT, |_, y| self.map(1.0 - y as f32 / (STRIP_HEIGHT - 1) as f32));
+        let buffer = SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(img.as_raw(), 1, STRIP_HEIGHT);
+        Image::from_rgba8_premultiplied(buffer)
+    }
+
+    fn stops(self) -> &'static [[f32; 3]] {
+        match self {
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Magma => &MAGMA,
+            Colormap::Grayscale => &GRAYSCALE,
+            Colormap::Cividis => &CIVIDIS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colormaps_span_their_endpoints() {
+        assert_eq!(Colormap::Viridis.map(0.0).0, [68, 1, 84, 255]);
+        assert_eq!(Colormap::Viridis.map(1.0).0, [253, 231, 37, 255]);
+        assert_eq!(Colormap::Magma.map(0.0).0, [0, 0, 4, 255]);
+        assert_eq!(Colormap::Magma.map(1.0).0, [252, 253, 191, 255]);
+        assert_eq!(Colormap::Grayscale.map(0.0).0, [0, 0, 0, 255]);
+        assert_eq!(Colormap::Grayscale.map(0.5).0, [128, 128, 128, 255]);
+        assert_eq!(Colormap::Grayscale.map(1.0).0, [255, 255, 255, 255]);
+        assert_eq!(Colormap::Cividis.map(0.0).0, [0, 34, 78, 255]);
+        assert_eq!(Colormap::Cividis.map(1.0).0, [254, 232, 56, 255]);
+        // Außerhalb von 0..=1 wird begrenzt
+        assert_eq!(Colormap::Magma.map(-1.0), Colormap::Magma.map(0.0));
+        assert_eq!(Colormap::Magma.map(2.0), Colormap::Magma.map(1.0));
+        assert_eq!(Colormap::from_index(3), Colormap::Cividis);
+        assert_eq!(Colormap::from_index(9), Colormap::Viridis);
+    }
+}
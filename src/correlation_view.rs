@@ -0,0 +1,57 @@
+// Heatmap der Korrelation aller Kanalpaare für Mehrkanal-Interfaces. Jede Zelle ist ein Pixel,
+// Slint vergrößert das Bild ohne Glättung auf die Größe der Anzeige.
+
+use image::{ImageBuffer, Rgba};
+use rmnc::MAX_CHANNELS;
+use slint::{Image, Rgba8Pixel, SharedPixelBuffer};
+
+/// Farbe für Paare ohne Messwert, z. B. mit einem stillen Kanal
+const NO_VALUE: Rgba<u8> = Rgba([64, 64, 64, 255]);
+
+/// Rendert die ersten `channels` Zeilen und Spalten von `matrix` als Bild mit einem Pixel je Paar,
+/// Kanal 0 oben links.
+pub fn render(matrix: &[[f32; MAX_CHANNELS]; MAX_CHANNELS], channels: usize) -> Image {
+    let img = render_buffer(matrix, channels);
+    let buffer = SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(img.as_raw(), img.width(), img.height());
+    Image::from_rgba8_premultiplied(buffer)
+}
+
+/// Wie `render`, liefert aber das Bild statt eines Slint-Images.
+pub fn render_buffer(matrix: &[[f32; MAX_CHANNELS]; MAX_CHANNELS], channels: usize) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    let size = channels.clamp(1, MAX_CHANNELS) as u32;
+    ImageBuffer::from_fn(size, size, |x, y| correlation_color(matrix[y as usize][x as usize]))
+}
+
+/// Farbe einer Korrelation wie beim Phasenmeter: grün bis +1, rot bis -1, schwarz bei 0.
+fn correlation_color(correlation: f32) -> Rgba<u8> {
+    if correlation.is_nan() {
+        return NO_VALUE;
+    }
+    let intensity = (correlation.abs().min(1.0) * 255.0).round() as u8;
+    if correlation >= 0.0 {
+        Rgba([0, intensity, 0, 255])
+    } else {
+        Rgba([intensity, 0, 0, 255])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correlation_view_colors_each_pair() {
+        // Kanal 1 gleichphasig, Kanal 2 gegenphasig zu Kanal 0, Kanal 3 still
+        let mut matrix = [[f32::NAN; MAX_CHANNELS]; MAX_CHANNELS];
+        for (a, b, correlation) in [(0, 0, 1.0), (1, 1, 1.0), (2, 2, 1.0), (0, 1, 1.0), (0, 2, -1.0), (1, 2, -1.0)] {
+            matrix[a][b] = correlation;
+            matrix[b][a] = correlation;
+        }
+        let img = render_buffer(&matrix, 4);
+        assert_eq!(img.dimensions(), (4, 4));
+        assert_eq!(img.get_pixel(1, 0).0, [0, 255, 0, 255]);
+        assert_eq!(img.get_pixel(2, 0).0, [255, 0, 0, 255]);
+        assert_eq!(img.get_pixel(3, 0), img.get_pixel(0, 3));
+        assert_ne!(img.get_pixel(3, 0).0, [0, 0, 0, 255]);
+    }
+}
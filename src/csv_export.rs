@@ -0,0 +1,55 @@
+// Export des Verlaufs als CSV für die Auswertung in anderen Programmen. Die Zeilen gehen einzeln
+// über einen gepufferten Writer in die Datei, auch ein sehr langer Verlauf wird also nicht erst
+// als Ganzes im Speicher aufgebaut.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use rmnc::{WaveformPoint, MAX_CHANNELS};
+
+/// Schreibt `points` als CSV nach `path` (siehe `write`).
+pub fn save<'a>(path: &Path, points: impl IntoIterator<Item = &'a WaveformPoint>, first_index: u64, channels: usize, chunk_duration: Duration) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write(&mut out, points, first_index, channels, chunk_duration)?;
+    out.flush()
+}
+
+/// Schreibt eine Kopfzeile und je Punkt eine Zeile mit seiner fortlaufenden Nummer ab
+/// `first_index`, der Zeit in Sekunden (Nummer mal `chunk_duration`) sowie kleinstem und größtem
+/// Wert jedes der ersten `channels` Kanäle.
+pub fn write<'a>(out: &mut impl Write, points: impl IntoIterator<Item = &'a WaveformPoint>, first_index: u64, channels: usize, chunk_duration: Duration) -> io::Result<()> {
+    let channels = channels.clamp(1, MAX_CHANNELS);
+    write!(out, "index,time_s")?;
+    for channel in 1..=channels {
+        write!(out, ",ch{}_min,ch{}_max", channel, channel)?;
+    }
+    writeln!(out)?;
+    for (index, point) in (first_index..).zip(points) {
+        write!(out, "{},{:.6}", index, index as f64 * chunk_duration.as_secs_f64())?;
+        for channel in 0..channels {
+            write!(out, ",{},{}", point.min[channel], point.max[channel])?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_duration;
+
+    #[test]
+    fn csv_export_writes_min_and_max_per_channel() {
+        let points = [
+            WaveformPoint { min: [-0.5, -0.25, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0], max: [0.5, 0.75, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0], ..WaveformPoint::default() },
+            WaveformPoint { min: [-1.0, 0.125, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0], max: [1.0, 0.25, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0], ..WaveformPoint::default() },
+        ];
+        let mut out = Vec::new();
+        // Die Nummern zählen ab dem ältesten Punkt im Verlauf weiter, 1024 Samples Stereo bei 48 kHz
+        write(&mut out, &points, 10, 2, chunk_duration(1024, 2, 48000)).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "index,time_s,ch1_min,ch1_max,ch2_min,ch2_max\n10,0.106667,-0.5,0.5,-0.25,0.75\n11,0.117333,-1,1,0.125,0.25\n");
+    }
+}
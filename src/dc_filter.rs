@@ -0,0 +1,45 @@
+// Einpoliger Hochpass gegen einen Gleichanteil im Eingang, wie ihn z. B. günstige Mikrofone
+// liefern. Der Zustand bleibt zwischen den Aufrufen erhalten, damit an Puffergrenzen kein
+// Sprung entsteht.
+
+use std::f32::consts::PI;
+
+/// DC-Blocker je Kanal: `y[n] = x[n] - x[n-1] + pole * y[n-1]`
+#[derive(Default)]
+pub struct DcBlocker {
+    /// (letzter Eingang, letzter Ausgang) je Kanal
+    state: Vec<(f32, f32)>,
+}
+
+impl DcBlocker {
+    pub const fn new() -> Self {
+        DcBlocker { state: Vec::new() }
+    }
+
+    /// Pol des Filters für die Grenzfrequenz `cutoff_hz` bei `sample_rate`.
+    pub fn pole(cutoff_hz: f32, sample_rate: u32) -> f32 {
+        (-2.0 * PI * cutoff_hz / sample_rate.max(1) as f32).exp()
+    }
+
+    /// Filtert interleavte Samples an Ort und Stelle, `samples[0]` gehört zu `first_channel`.
+    /// Ändert sich die Kanalzahl, beginnt das Filter von vorn.
+    pub fn process(&mut self, samples: &mut [f32], channels: usize, first_channel: usize, pole: f32) {
+        let channels = channels.max(1);
+        if self.state.len() != channels {
+            self.state.clear();
+            self.state.resize(channels, (0.0, 0.0));
+        }
+        for (idx, sample) in samples.iter_mut().enumerate() {
+            let (last_in, last_out) = &mut self.state[(first_channel + idx) % channels];
+            let out = *sample - *last_in + pole * *last_out;
+            *last_in = *sample;
+            *last_out = out;
+            *sample = out;
+        }
+    }
+
+    /// Verwirft den Zustand aller Kanäle.
+    pub fn reset(&mut self) {
+        self.state.fill((0.0, 0.0));
+    }
+}
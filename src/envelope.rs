@@ -0,0 +1,125 @@
+// Hüllkurvenfolger vor der Reduktion für eine ruhigere, gehörnähere Anzeige, z. B. bei Sprache.
+// Ein einpoliger Detektor folgt dem gleichgerichteten Signal mit getrennter Attack- und
+// Release-Zeit. Jedes Sample wird durch die Hüllkurve mit seinem eigenen Vorzeichen ersetzt,
+// damit die Anzeige symmetrisch um die Mittellinie bleibt. Der Zustand bleibt je Kanal zwischen
+// den Aufrufen erhalten.
+
+use std::time::Duration;
+
+/// Voreinstellungen für Attack- und Release-Zeit
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EnvelopePreset {
+    /// Folgt Silben, glättet aber die einzelnen Perioden der Stimme
+    #[default]
+    Speech,
+    /// Schneller Anstieg für Transienten, langsamer Abfall
+    Music,
+}
+
+impl EnvelopePreset {
+    /// Voreinstellung für den Index in der UI, unbekannte Werte ergeben Sprache.
+    pub fn from_index(index: i32) -> Self {
+        match index {
+            1 => EnvelopePreset::Music,
+            _ => EnvelopePreset::Speech,
+        }
+    }
+
+    /// (Attack, Release)
+    pub fn timing(self) -> (Duration, Duration) {
+        match self {
+            EnvelopePreset::Speech => (Duration::from_millis(10), Duration::from_millis(100)),
+            EnvelopePreset::Music => (Duration::from_millis(5), Duration::from_millis(300)),
+        }
+    }
+}
+
+/// Koeffizienten des Detektors, für eine feste Abtastrate berechnet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnvelopeSettings {
+    /// Anteil der alten Hüllkurve je Sample bei steigendem Betrag, 0 folgt sofort
+    pub attack: f32,
+    /// Wie `attack` bei fallendem Betrag
+    pub release: f32,
+}
+
+impl EnvelopeSettings {
+    /// Zeitkonstanten `attack` und `release` bei `sample_rate`.
+    pub fn new(attack: Duration, release: Duration, sample_rate: u32) -> Self {
+        let coefficient = |duration: Duration| {
+            let samples = duration.as_secs_f32() * sample_rate as f32;
+            if samples > 0.0 { (-1.0 / samples).exp() } else { 0.0 }
+        };
+        EnvelopeSettings { attack: coefficient(attack), release: coefficient(release) }
+    }
+}
+
+#[derive(Default)]
+pub struct EnvelopeFollower {
+    /// Aktuelle Hüllkurve je Kanal
+    envelope: Vec<f32>,
+}
+
+impl EnvelopeFollower {
+    pub const fn new() -> Self {
+        EnvelopeFollower { envelope: Vec::new() }
+    }
+
+    /// Ersetzt interleavte Samples an Ort und Stelle durch ihre Hüllkurve, `samples[0]` gehört zu
+    /// `first_channel`. Ändert sich die Kanalzahl, beginnen alle Kanäle bei 0.
+    pub fn process(&mut self, samples: &mut [f32], channels: usize, first_channel: usize, settings: EnvelopeSettings) {
+        let channels = channels.max(1);
+        if self.envelope.len() != channels {
+            self.envelope.clear();
+            self.envelope.resize(channels, 0.0);
+        }
+        for (idx, sample) in samples.iter_mut().enumerate() {
+            let envelope = &mut self.envelope[(first_channel + idx) % channels];
+            let level = sample.abs();
+            let coefficient = if level > *envelope { settings.attack } else { settings.release };
+            *envelope = level + coefficient * (*envelope - level);
+            *sample = envelope.copysign(*sample);
+        }
+    }
+
+    /// Setzt die Hüllkurve in allen Kanälen auf 0.
+    pub fn reset(&mut self) {
+        self.envelope.fill(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope_follows_the_rectified_signal_per_channel() {
+        let settings = EnvelopeSettings::new(Duration::from_millis(1), Duration::from_millis(10), 1000);
+        assert!((settings.attack - (-1f32).exp()).abs() < 1e-6);
+        assert!((settings.release - (-0.1f32).exp()).abs() < 1e-6);
+        assert_eq!(EnvelopeSettings::new(Duration::ZERO, Duration::ZERO, 1000), EnvelopeSettings { attack: 0.0, release: 0.0 });
+
+        // Links ein Rechteck mit wechselndem Vorzeichen, rechts Stille
+        let mut follower = EnvelopeFollower::new();
+        let mut samples: Vec<f32> = (0..40).flat_map(|i| [if i % 2 == 0 { 1.0 } else { -1.0 }, 0.0]).collect();
+        follower.process(&mut samples, 2, 0, settings);
+        // Der Betrag steigt mit der Attack-Zeit an, das Vorzeichen bleibt erhalten
+        assert!((samples[0] - (1.0 - (-1f32).exp())).abs() < 1e-6);
+        assert!(samples[2] < 0.0 && samples[2].abs() > samples[0]);
+        assert!((samples[78].abs() - 1.0).abs() < 1e-3);
+        assert!(samples.iter().skip(1).step_by(2).all(|&sample| sample == 0.0));
+
+        // Danach fällt die Hüllkurve mit der Release-Zeit, auch über Aufrufe hinweg
+        let mut silence = [0.0f32; 20];
+        follower.process(&mut silence, 2, 0, settings);
+        assert!((silence[0] - (-0.1f32).exp()).abs() < 1e-3);
+        assert!((silence[18] - (-1f32).exp()).abs() < 1e-3);
+
+        follower.reset();
+        let mut silence = [0.0f32; 2];
+        follower.process(&mut silence, 2, 0, settings);
+        assert_eq!(silence, [0.0, 0.0]);
+        let (attack, release) = EnvelopePreset::Speech.timing();
+        assert!(attack < release);
+    }
+}
@@ -0,0 +1,60 @@
+// Betrieb ohne Fenster für Skripte und CI: der Stream läuft eine feste Dauer, die reduzierten
+// Punkte werden als CSV ausgegeben. Verarbeitung wie mit UI, nur ein anderer Abnehmer der Queue.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rmnc::params::Params;
+use rmnc::{AudioConfig, Meters, WaveformCapture, WaveformPoint, MAX_CHANNELS};
+use rtrb::RingBuffer;
+
+use crate::POINT_QUEUE_CAPACITY;
+
+/// Abstand, in dem die Queue geleert wird
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Nimmt `duration` lang mit `audio_config` auf und schreibt je Punkt eine Zeile mit Zeit in
+/// Sekunden und dem Pegel (größte Abweichung von 0) jedes Kanals nach `output` bzw. stdout.
+pub fn run(audio_config: &AudioConfig, chunk_size: usize, duration: Duration, output: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    let (mut producer, mut points) = RingBuffer::<WaveformPoint>::new(POINT_QUEUE_CAPACITY);
+    // Ohne Spektrogramm und Oszilloskop braucht es keine Mono-Samples
+    let capture = WaveformCapture::start(audio_config, Arc::new(Params::new(chunk_size)), Arc::new(Meters::new()), None, move |point| {
+        let _ = producer.push(point);
+    })?;
+    let info = capture.info();
+    let channels = info.channels.min(MAX_CHANNELS);
+
+    write!(out, "time_s")?;
+    for channel in 1..=channels {
+        write!(out, ",ch{}", channel)?;
+    }
+    writeln!(out)?;
+
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        std::thread::sleep(POLL_INTERVAL);
+        if capture.device_lost() {
+            return Err("audio device lost".into());
+        }
+        while let Ok(point) = points.pop() {
+            // Zeit laut Gerät, ausgefallene Puffer erscheinen als Sprung
+            write!(out, "{:.6}", point.time.as_secs_f64())?;
+            for channel in 0..channels {
+                write!(out, ",{}", point.level(channel))?;
+            }
+            writeln!(out)?;
+        }
+    }
+    capture.stop();
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,39 @@
+// Verteilung der Pegel im sichtbaren Ausschnitt als Histogramm. Jeder Punkt trägt den Pegel
+// seines Chunks (größter Betrag) je angezeigtem Kanal bei. Die Klassen teilen entweder 0 bis
+// Vollausschlag linear oder die dB-Skala von ihrer Untergrenze bis 0 dBFS gleichmäßig auf.
+
+use crate::render_plot::AmplitudeScale;
+
+/// Anzahl der Klassen
+pub const BINS: usize = 64;
+
+/// Zählt `levels` (±1.0 = 0 dBFS) in `bins` Klassen, leise Pegel vorne. Werte über Vollausschlag
+/// landen in der letzten Klasse, Werte unter der dB-Untergrenze in der ersten.
+pub fn count(levels: impl IntoIterator<Item = f32>, bins: usize, scale: AmplitudeScale) -> Vec<u32> {
+    let bins = bins.max(1);
+    let mut counts = vec![0; bins];
+    for level in levels {
+        let fraction = match scale {
+            AmplitudeScale::Linear => level.clamp(0.0, 1.0),
+            AmplitudeScale::Db { .. } => scale.lane_fraction(level),
+        };
+        // NaN ergibt Klasse 0
+        counts[((fraction * bins as f32) as usize).min(bins - 1)] += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_bins_levels_linearly_or_in_db() {
+        let levels = [0.0, 0.1, 0.26, 0.5, 0.99, 1.0, 1.5];
+        assert_eq!(count(levels, 4, AmplitudeScale::Linear), vec![2, 1, 1, 3]);
+        // dB über -60..0 dBFS in Klassen zu 20 dB, Stille und alles unter -60 dB in der ersten
+        let db = AmplitudeScale::Db { floor_db: -60.0 };
+        assert_eq!(count([0.0, 0.0001, 0.001, 0.011, 0.05, 0.5, 1.0], 3, db), vec![3, 2, 2]);
+        assert_eq!(count([0.5; 10], 8, db).iter().sum::<u32>(), 10);
+    }
+}
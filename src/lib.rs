@@ -0,0 +1,844 @@
+// Erfassung und Reduktion eines Audio-Eingangs, unabhängig von Slint und der UI.
+//
+// `WaveformCapture` öffnet ein Gerät und übergibt jeden reduzierten Punkt an einen Callback des
+// Aufrufers. Die Anzeige in `main.rs` ist nur ein Abnehmer davon, ebenso der Betrieb ohne
+// Fenster. Die Bausteine der Reduktion (`process_audio`, `reduce_chunks`, die Filter) sind
+// öffentlich, damit sie sich auch ohne Gerät, z. B. auf Dateien oder in Tests, verwenden lassen.
+
+pub mod a_weighting;
+pub mod audio_error;
+mod biquad;
+pub mod capture;
+pub mod dc_filter;
+pub mod envelope;
+pub mod loudness;
+pub mod noise_gate;
+pub mod params;
+pub mod recorder;
+pub mod resample;
+#[cfg(feature = "simd")]
+pub mod simd_reduce;
+pub mod wav_file;
+
+use std::time::Duration;
+
+use a_weighting::AWeighting;
+use dc_filter::DcBlocker;
+use envelope::{EnvelopeFollower, EnvelopeSettings};
+use noise_gate::{GateSettings, NoiseGate};
+use resample::Resampler;
+
+pub use capture::{list_input_devices, list_loopback_devices, AudioConfig, AudioSource, Meters, Pipeline, StreamInfo, WaveformCapture};
+
+/// Maximale Anzahl Kanäle, die je Punkt gespeichert werden; weitere Kanäle werden ignoriert
+pub const MAX_CHANNELS: usize = 8;
+
+/// Ein reduzierter Punkt: kleinster und größter Wert je Kanal mit Vorzeichen, damit die Anzeige
+/// auch unsymmetrische Signale als Hüllkurve um die Mittellinie zeigen kann. Unbenutzte Kanäle
+/// sind 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WaveformPoint {
+    pub min: [f32; MAX_CHANNELS],
+    pub max: [f32; MAX_CHANNELS],
+    /// Zeit des ersten Frames im Chunk seit Beginn des Streams bzw. der Datei. Im Live-Betrieb
+    /// aus den Zeitstempeln des Geräts, ausgefallene Puffer erscheinen daher als Sprung.
+    pub time: Duration,
+    /// Vor diesem Punkt fehlen Samples, z. B. weil das Gerät einen Puffer verloren hat (xrun)
+    pub gap: bool,
+}
+
+impl WaveformPoint {
+    /// Punkt, der je Kanal symmetrisch von `-level` bis `level` reicht, z. B. für den Effektivwert.
+    pub fn from_levels(levels: [f32; MAX_CHANNELS]) -> Self {
+        WaveformPoint { min: levels.map(|level| -level), max: levels, time: Duration::ZERO, gap: false }
+    }
+
+    /// Größte Abweichung von 0 in Kanal `channel`, für Pegelanzeigen und Skalierung.
+    pub fn level(&self, channel: usize) -> f32 {
+        self.max[channel].max(-self.min[channel]).max(0.0)
+    }
+
+    /// `level` für alle Kanäle.
+    pub fn levels(&self) -> [f32; MAX_CHANNELS] {
+        std::array::from_fn(|channel| self.level(channel))
+    }
+}
+
+
+/// Art der Reduktion eines Chunks auf einen Wert je Kanal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WaveformMode {
+    /// Kleinster und größter Wert mit Vorzeichen
+    #[default]
+    Peak,
+    /// Effektivwert (Root Mean Square)
+    Rms,
+}
+
+impl WaveformMode {
+    /// Art für `mode` in der UI bzw. in `Params`, unbekannte Werte ergeben `Peak`.
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => WaveformMode::Rms,
+            _ => WaveformMode::Peak,
+        }
+    }
+}
+
+/// Vollausschlag eines Sample-Formats: Erkennung von Übersteuerung und Normierung auf ±1.0. Die
+/// Konstanten der Implementierungen bilden die Tabelle der Formate. Integer-Formate mit Füllbits,
+/// z. B. 24 Bit rechtsbündig in einem 32-Bit-Container, nutzen nur einen Teil des Bereichs und
+/// brauchen mit `full_scale` einen kleineren Bezug.
+pub trait FullScale: Copy {
+    /// Betrag, der bei voller Nutzung des Formats ±1.0 entspricht
+    const FULL_SCALE: f64;
+    /// Breite des Formats in Bit, 0 bei Gleitkomma
+    const BITS: u32;
+
+    /// Abstand zur Ruhelage ohne Normierung
+    fn centered(self) -> f64;
+
+    /// Bezug für `normalize_to`: bei `significant_bits` genutzten Bits eines Integer-Formats
+    /// 2^(significant_bits - 1), sonst `FULL_SCALE`. Angaben, die das Format nicht unterschreiten,
+    /// gelten nicht, ebenso bei Gleitkomma.
+    fn full_scale(significant_bits: Option<u32>) -> f64 {
+        match significant_bits {
+            Some(bits) if bits >= 2 && bits < Self::BITS => 2f64.powi(bits as i32 - 1),
+            _ => Self::FULL_SCALE,
+        }
+    }
+
+    /// Ob der Wert bei Bezug `full_scale` den Vollausschlag erreicht. Bei Integer-Formaten liegt
+    /// der größte positive Wert einen Schritt unter dem Bezug.
+    fn is_full_scale_of(self, full_scale: f64) -> bool {
+        let value = self.centered();
+        let step = if Self::BITS > 0 { 1.0 } else { 0.0 };
+        value >= full_scale - step || value <= -full_scale
+    }
+
+    fn is_full_scale(self) -> bool {
+        self.is_full_scale_of(Self::FULL_SCALE)
+    }
+
+    /// Wert als f32, `full_scale` entspricht ±1.0
+    fn normalize_to(self, full_scale: f64) -> f32 {
+        (self.centered() / full_scale) as f32
+    }
+
+    /// Wert als f32, Vollausschlag des Formats entspricht ±1.0
+    fn normalize(self) -> f32 {
+        self.normalize_to(Self::FULL_SCALE)
+    }
+}
+
+impl FullScale for f32 {
+    const FULL_SCALE: f64 = 1.0;
+    const BITS: u32 = 0;
+
+    fn centered(self) -> f64 {
+        self as f64
+    }
+}
+
+impl FullScale for i8 {
+    const FULL_SCALE: f64 = 128.0;
+    const BITS: u32 = 8;
+
+    fn centered(self) -> f64 {
+        self as f64
+    }
+}
+
+impl FullScale for i16 {
+    const FULL_SCALE: f64 = 32768.0;
+    const BITS: u32 = 16;
+
+    fn centered(self) -> f64 {
+        self as f64
+    }
+}
+
+impl FullScale for i32 {
+    const FULL_SCALE: f64 = 2147483648.0;
+    const BITS: u32 = 32;
+
+    fn centered(self) -> f64 {
+        self as f64
+    }
+}
+
+impl FullScale for u16 {
+    const FULL_SCALE: f64 = 32768.0;
+    const BITS: u32 = 16;
+
+    /// Die Ruhelage liegt bei 32768
+    fn centered(self) -> f64 {
+        self as f64 - 32768.0
+    }
+}
+
+/// Zustand der Reduktion zwischen zwei Puffern. Gehört dem Callback eines Streams und
+/// beginnt daher mit jedem neuen Stream leer.
+#[derive(Default)]
+pub struct ProcessState {
+    /// Überstehende Samples vom letzten Aufruf
+    remainder: Vec<f32>,
+    /// Arbeitspuffer für die konvertierten Samples eines Aufrufs. Er behält wie `remainder` seine
+    /// Kapazität, damit im eingeschwungenen Zustand keine Allokationen im Audio-Thread anfallen.
+    /// Die Filter laufen auf `filtered`, hier bleiben die Samples nach Umrechnung und
+    /// Phasendrehung für Messungen wie die Korrelation erhalten.
+    converted: Vec<f32>,
+    /// Arbeitspuffer für die gefilterten Samples vor der Reduktion, wie `converted`
+    filtered: Vec<f32>,
+    /// Chunk-Größe, zu der `remainder` gehört
+    last_chunk_size: usize,
+    dc_blocker: DcBlocker,
+    a_weighting: AWeighting,
+    noise_gate: NoiseGate,
+    envelope: EnvelopeFollower,
+    /// Rechnet vor Filtern und Reduktion auf die interne Abtastrate um, falls gesetzt
+    resampler: Option<Resampler>,
+    /// Arbeitspuffer für die umgerechneten Samples, wie `converted`
+    resampled: Vec<f32>,
+    /// Genutzte Bits bei Integer-Formaten mit Füllbits (siehe `FullScale::full_scale`)
+    significant_bits: Option<u32>,
+}
+
+impl ProcessState {
+    /// Zustand, der die Samples vor der Reduktion von `input_rate` auf `output_rate` umrechnet.
+    /// Gleiche Raten brauchen keinen Resampler.
+    pub fn resampling(input_rate: u32, output_rate: u32, channels: usize) -> Self {
+        let resampler = (input_rate != output_rate).then(|| Resampler::new(input_rate, output_rate, channels));
+        ProcessState { resampler, ..ProcessState::default() }
+    }
+
+    /// Normiert Integer-Samples auf `significant_bits` statt auf die Breite ihres Formats (siehe
+    /// `FullScale::full_scale`).
+    pub fn set_significant_bits(&mut self, significant_bits: Option<u32>) {
+        self.significant_bits = significant_bits;
+    }
+
+    /// Frames im Rest, die auf den nächsten Chunk mit `chunk_size` Samples warten. Gehört der
+    /// Rest zu einer anderen Chunk-Größe, verwirft ihn `process_audio`, dann 0.
+    pub fn pending_frames(&self, chunk_size: usize, channels: usize) -> usize {
+        if self.last_chunk_size != aligned_chunk_size(chunk_size, channels) {
+            return 0;
+        }
+        self.remainder.len() / channels.max(1)
+    }
+
+    /// Ob das Noise-Gate nach dem letzten Aufruf in mindestens einem Kanal offen ist.
+    pub fn gate_open(&self) -> bool {
+        self.noise_gate.is_open()
+    }
+
+    /// Verwirft den Rest und den Filterzustand, z. B. nach einer Pause. Ein angefangenes Frame
+    /// aus `channels` Kanälen bleibt stehen, der nächste Puffer setzt es fort.
+    pub fn reset(&mut self, channels: usize) {
+        discard_whole_frames(&mut self.remainder, channels);
+        self.dc_blocker.reset();
+        self.a_weighting.reset();
+        self.noise_gate.reset();
+        self.envelope.reset();
+        if let Some(resampler) = self.resampler.as_mut() {
+            resampler.reset();
+        }
+    }
+}
+
+/// Reduziert die interleavten Samples auf einen Punkt je Chunk mit einem Wert je Kanal
+/// gemäß `mode` und übergibt die Punkte der Reihe nach an `emit`.
+/// Mehr als `MAX_CHANNELS` Kanäle werden ignoriert, außer bei `downmix` (siehe `reduce_chunk`).
+/// Kanäle, deren Bit in `inverted` gesetzt ist, werden zuerst in der Phase gedreht. Mit
+/// `dc_pole` läuft jeder Kanal danach durch einen DC-Blocker (siehe `DcBlocker`), mit
+/// `a_weighting` (Abtastrate in Hz) durch die A-Bewertung (siehe `AWeighting`), mit `gate`
+/// durch das Noise-Gate (siehe `NoiseGate`) und mit `envelope` zuletzt durch den
+/// Hüllkurvenfolger (siehe `EnvelopeFollower`). Rechnet `state` auf eine interne Rate um (siehe
+/// `ProcessState::resampling`), gelten alle für diese Rate.
+/// Ändert sich `chunk_size` zwischen zwei Aufrufen, wird der Rest des letzten Aufrufs verworfen.
+/// Liefert `true`, wenn ein Sample in `data` den Vollausschlag erreicht hat (vor der Konvertierung,
+/// bei Füllbits gemessen an den genutzten Bits, siehe `ProcessState::set_significant_bits`).
+#[allow(clippy::too_many_arguments)]
+pub fn process_audio<T: cpal::Sample + FullScale>(data: &[T], channels: usize, chunk_size: usize, mode: WaveformMode, downmix: bool, inverted: u32, dc_pole: Option<f32>, a_weighting: Option<u32>, gate: Option<GateSettings>, envelope: Option<EnvelopeSettings>, state: &mut ProcessState, emit: impl FnMut(WaveformPoint)) -> bool {
+    let channels = channels.max(1);
+    let chunk_size = aligned_chunk_size(chunk_size, channels);
+    let full_scale = T::full_scale(state.significant_bits);
+    let clipped = data.iter().any(|&s| s.is_full_scale_of(full_scale));
+
+    // Der Rest gehört zur alten Chunk-Größe und würde den ersten neuen Chunk verfälschen
+    if std::mem::replace(&mut state.last_chunk_size, chunk_size) != chunk_size {
+        discard_whole_frames(&mut state.remainder, channels);
+    }
+
+    // Konvertiere eingehende Daten in f32 (±1.0 bei Vollausschlag)
+    state.converted.clear();
+    state.converted.extend(data.iter().map(|&s| s.normalize_to(full_scale)));
+    // Der Resampler liefert nur ganze Frames und puffert ein angefangenes selbst, der Rest bleibt
+    // daher auch nach der Umrechnung an Frames ausgerichtet
+    if let Some(resampler) = state.resampler.as_mut() {
+        state.resampled.clear();
+        resampler.process(&state.converted, &mut state.resampled);
+        std::mem::swap(&mut state.converted, &mut state.resampled);
+    }
+
+    // Der Rest beginnt immer mit Kanal 0 und ist bereits gefiltert
+    invert_channels(&mut state.converted, channels, state.remainder.len() % channels, inverted);
+    state.filtered.clear();
+    state.filtered.extend_from_slice(&state.converted);
+    match dc_pole {
+        Some(pole) => state.dc_blocker.process(&mut state.filtered, channels, state.remainder.len() % channels, pole),
+        None => state.dc_blocker.reset(),
+    }
+    match a_weighting {
+        Some(sample_rate) => state.a_weighting.process(&mut state.filtered, channels, state.remainder.len() % channels, sample_rate),
+        None => state.a_weighting.reset(),
+    }
+    match gate {
+        Some(settings) => state.noise_gate.process(&mut state.filtered, channels, state.remainder.len() % channels, settings),
+        None => state.noise_gate.reset(),
+    }
+    match envelope {
+        Some(settings) => state.envelope.process(&mut state.filtered, channels, state.remainder.len() % channels, settings),
+        None => state.envelope.reset(),
+    }
+
+    reduce_chunks(&state.filtered, channels, chunk_size, mode, downmix, &mut state.remainder, emit);
+    clipped
+}
+
+/// Verwirft die vollständigen Frames in `remainder`. Ein angefangenes Frame am Ende bleibt, sonst
+/// landeten alle folgenden Samples im falschen Kanal.
+fn discard_whole_frames(remainder: &mut Vec<f32>, channels: usize) {
+    let whole = remainder.len() - remainder.len() % channels.max(1);
+    remainder.drain(..whole);
+}
+
+/// Negiert an Ort und Stelle die interleavten Samples der Kanäle, deren Bit in `inverted` gesetzt
+/// ist. `samples[0]` gehört zu `first_channel`.
+fn invert_channels(samples: &mut [f32], channels: usize, first_channel: usize, inverted: u32) {
+    if inverted == 0 {
+        return;
+    }
+    for (idx, sample) in samples.iter_mut().enumerate() {
+        let channel = (first_channel + idx) % channels;
+        if inverted.checked_shr(channel as u32).is_some_and(|bits| bits & 1 != 0) {
+            *sample = -*sample;
+        }
+    }
+}
+
+/// Hängt `samples` an `remainder` an, reduziert alle vollständigen Chunks mit `reduce_chunk` und
+/// übergibt die Punkte der Reihe nach an `emit`. Unvollständige Samples bleiben in `remainder`.
+/// Chunks bestehen aus ganzen Frames, daher beginnt auch der Rest mit Kanal 0, selbst wenn
+/// `samples` mitten in einem Frame endet.
+pub fn reduce_chunks(samples: &[f32], channels: usize, chunk_size: usize, mode: WaveformMode, downmix: bool, remainder: &mut Vec<f32>, mut emit: impl FnMut(WaveformPoint)) {
+    let channels = channels.max(1);
+    let chunk_size = aligned_chunk_size(chunk_size, channels);
+    // Die Kapazität bleibt erhalten, es wird nur beim ersten Aufruf bzw. bei größeren Buffern allokiert
+    remainder.extend_from_slice(samples);
+    let processed = remainder.len() - remainder.len() % chunk_size;
+    for chunk in remainder[..processed].chunks_exact(chunk_size) {
+        emit(reduce_chunk(chunk, channels, mode, downmix));
+    }
+    // Überstehende Samples für den nächsten Aufruf an den Anfang verschieben (ohne Allokation)
+    remainder.drain(..processed);
+}
+
+/// Normierte Kreuzkorrelation von Kanal 0 und 1 der interleavten `samples` im Bereich -1..=1.
+/// +1 bedeutet gleichphasig (mono-kompatibel), -1 gegenphasig, d.h. die Summe löscht sich aus.
+/// `None` bei weniger als zwei Kanälen oder wenn ein Kanal still ist.
+pub fn stereo_correlation(samples: &[f32], channels: usize) -> Option<f32> {
+    if channels < 2 {
+        return None;
+    }
+    let (mut sum_lr, mut sum_ll, mut sum_rr) = (0.0f64, 0.0f64, 0.0f64);
+    for frame in samples.chunks_exact(channels) {
+        let (left, right) = (frame[0] as f64, frame[1] as f64);
+        sum_lr += left * right;
+        sum_ll += left * left;
+        sum_rr += right * right;
+    }
+    let energy = (sum_ll * sum_rr).sqrt();
+    if energy < 1e-12 {
+        return None;
+    }
+    Some((sum_lr / energy).clamp(-1.0, 1.0) as f32)
+}
+
+/// Normierte Kreuzkorrelation aller Kanalpaare der interleavten `samples` wie bei
+/// `stereo_correlation`, Eintrag `[a][b]` für Kanal `a` und `b`. NaN für Paare, in denen ein
+/// Kanal still ist, und für Kanäle ab `channels`. Mehr als `MAX_CHANNELS` Kanäle werden ignoriert.
+pub fn correlation_matrix(samples: &[f32], channels: usize) -> [[f32; MAX_CHANNELS]; MAX_CHANNELS] {
+    let channels = channels.max(1);
+    let used = channels.min(MAX_CHANNELS);
+    let mut sums = [[0.0f64; MAX_CHANNELS]; MAX_CHANNELS];
+    for frame in samples.chunks_exact(channels) {
+        for a in 0..used {
+            for b in a..used {
+                sums[a][b] += frame[a] as f64 * frame[b] as f64;
+            }
+        }
+    }
+    let mut matrix = [[f32::NAN; MAX_CHANNELS]; MAX_CHANNELS];
+    for a in 0..used {
+        for b in a..used {
+            // Jedes Paar für sich, ein stiller Kanal macht nur seine Zeile und Spalte ungültig
+            let energy = (sums[a][a] * sums[b][b]).sqrt();
+            if energy >= 1e-12 {
+                let correlation = (sums[a][b] / energy).clamp(-1.0, 1.0) as f32;
+                matrix[a][b] = correlation;
+                matrix[b][a] = correlation;
+            }
+        }
+    }
+    matrix
+}
+
+/// Rundet `chunk_size` auf ganze Frames ab (mindestens ein Frame), damit jeder Chunk mit
+/// Kanal 0 beginnt.
+pub fn aligned_chunk_size(chunk_size: usize, channels: usize) -> usize {
+    let channels = channels.max(1);
+    (chunk_size / channels).max(1) * channels
+}
+
+/// Reduziert einen Chunk interleavter Samples gemäß `mode` auf (kleinsten, größten) Wert je Kanal.
+/// Bei `downmix` wird jedes Frame über alle Kanäle gemittelt und nur diese Mono-Summe reduziert;
+/// der Wert steht dann in jedem Kanal, damit die Anzeige unverändert funktioniert.
+///
+/// Mit dem Feature `simd` läuft `WaveformMode::Peak` ohne Downmix über `simd_reduce::peak`,
+/// soweit die Kanalzahl passt, sonst über `reduce_chunk_scalar`. Das Ergebnis ist dasselbe.
+pub fn reduce_chunk(chunk: &[f32], channels: usize, mode: WaveformMode, downmix: bool) -> WaveformPoint {
+    #[cfg(feature = "simd")]
+    if mode == WaveformMode::Peak && !downmix {
+        if let Some((min, max)) = simd_reduce::peak(chunk, channels) {
+            return WaveformPoint { min, max, ..WaveformPoint::default() };
+        }
+    }
+    reduce_chunk_scalar(chunk, channels, mode, downmix)
+}
+
+/// Skalare Reduktion wie `reduce_chunk`, unabhängig vom Feature `simd`, als Referenz und für die
+/// Benchmarks.
+pub fn reduce_chunk_scalar(chunk: &[f32], channels: usize, mode: WaveformMode, downmix: bool) -> WaveformPoint {
+    let mut point = WaveformPoint::default();
+    let used = channels.min(MAX_CHANNELS);
+    if downmix {
+        let mono = chunk.chunks_exact(channels).map(|frame| frame.iter().sum::<f32>() / channels as f32);
+        let (min, max) = reduce_samples(mono, mode);
+        point.min[..used].fill(min);
+        point.max[..used].fill(max);
+        return point;
+    }
+    for (channel, (min, max)) in point.min.iter_mut().zip(point.max.iter_mut()).enumerate().take(used) {
+        (*min, *max) = reduce_samples(chunk.iter().skip(channel).step_by(channels).copied(), mode);
+    }
+    point
+}
+
+/// Reduziert die Samples eines Kanals gemäß `mode` auf (kleinsten, größten) Wert.
+fn reduce_samples(samples: impl Iterator<Item = f32> + Clone, mode: WaveformMode) -> (f32, f32) {
+    match mode {
+        WaveformMode::Peak => {
+            // Mit Vorzeichen, ein Chunk ganz über oder unter 0 berührt die Mittellinie nicht
+            let (min, max) = samples.fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), s| (min.min(s), max.max(s)));
+            if min > max { (0.0, 0.0) } else { (min, max) }
+        }
+        WaveformMode::Rms => {
+            // Der Effektivwert hat kein Vorzeichen und wird symmetrisch gespeichert
+            let (sum_of_squares, count) = samples.fold((0.0, 0usize), |(sum, count), b| (sum + b * b, count + 1));
+            let rms = if count > 0 { (sum_of_squares / count as f32).sqrt() } else { 0.0 };
+            (-rms, rms)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Chunk-Größe der Tests, wie die Voreinstellung der Anzeige
+    const CHUNK_SIZE: usize = 2048;
+
+    fn reduce(data: &[f32], channels: usize, mode: WaveformMode) -> Vec<WaveformPoint> {
+        let mut points = Vec::new();
+        process_audio(data, channels, CHUNK_SIZE, mode, false, 0, None, None, None, None, &mut ProcessState::default(), |point| points.push(point));
+        points
+    }
+
+    #[test]
+    fn mono_input_produces_finite_points() {
+        let data: Vec<f32> = (0..4096).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
+
+        let waveform = reduce(&data, 1, WaveformMode::Peak);
+        assert_eq!(waveform.len(), 2);
+        for point in waveform.iter() {
+            assert!(point.min.iter().chain(&point.max).all(|value| value.is_finite()));
+            assert!(point.level(0) > 0.0);
+        }
+    }
+
+    #[test]
+    fn four_channel_input_is_reduced_per_channel() {
+        // Kanal k hat abwechselnd die Amplitude +/-(k + 1) * 0.1
+        let data: Vec<f32> = (0..2048)
+            .map(|i| {
+                let channel = i % 4;
+                let sign = if (i / 4) % 2 == 0 { 1.0 } else { -1.0 };
+                sign * (channel + 1) as f32 * 0.1
+            })
+            .collect();
+
+        let waveform = reduce(&data, 4, WaveformMode::Peak);
+        assert_eq!(waveform.len(), 1);
+        for (channel, &value) in waveform[0].levels().iter().take(4).enumerate() {
+            assert!((value - (channel + 1) as f32 * 0.1).abs() < 1e-6);
+        }
+        assert!(waveform[0].levels()[4..].iter().all(|&value| value == 0.0));
+    }
+
+    #[test]
+    fn rms_of_sine_chunk_matches_analytic_value() {
+        // Genau 8 Perioden pro Chunk, damit der Effektivwert exakt 1/sqrt(2) ist
+        let data: Vec<f32> = (0..CHUNK_SIZE)
+            .map(|i| (2.0 * std::f32::consts::PI * 8.0 * i as f32 / CHUNK_SIZE as f32).sin())
+            .collect();
+
+        let waveform = reduce(&data, 1, WaveformMode::Rms);
+        assert_eq!(waveform.len(), 1);
+        assert!((waveform[0].level(0) - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-4);
+        assert_eq!(waveform[0].min[0], -waveform[0].max[0]);
+    }
+
+    #[test]
+    fn reduce_chunks_carries_partial_chunks_over() {
+        let mut remainder = Vec::new();
+        let mut points = Vec::new();
+        // Stereo mit Chunks aus zwei Frames: der erste Aufruf reicht nur für einen Chunk
+        reduce_chunks(&[0.1, -0.2, -0.3, 0.4, 0.5, 0.6], 2, 4, WaveformMode::Peak, false, &mut remainder, |point| points.push(point));
+        assert_eq!(remainder, [0.5, 0.6]);
+        reduce_chunks(&[-0.7, 0.0, 0.2], 2, 4, WaveformMode::Peak, false, &mut remainder, |point| points.push(point));
+        assert_eq!(remainder, [0.2]);
+        assert_eq!(points.len(), 2);
+        assert_eq!(&points[0].levels()[..2], &[0.3, 0.4]);
+        assert_eq!(&points[1].levels()[..2], &[0.7, 0.6]);
+        assert_eq!((&points[0].min[..2], &points[0].max[..2]), (&[-0.3, -0.2][..], &[0.1, 0.4][..]));
+        assert!(points.iter().all(|point| point.levels()[2..].iter().all(|&value| value == 0.0)));
+    }
+
+    /// Teilt `samples` in aufeinanderfolgende Stücke mit den Längen aus `lengths` (zyklisch).
+    fn split_by<'a>(samples: &'a [f32], lengths: &[usize]) -> Vec<&'a [f32]> {
+        let mut pieces = Vec::new();
+        let mut rest = samples;
+        for &length in lengths.iter().cycle() {
+            if rest.is_empty() {
+                break;
+            }
+            let (piece, tail) = rest.split_at(length.min(rest.len()));
+            pieces.push(piece);
+            rest = tail;
+        }
+        pieces
+    }
+
+    #[test]
+    fn reduce_chunks_in_pieces_matches_the_whole_signal() {
+        let signal: Vec<f32> = (0..10_007).map(|i| (i as f32 * 0.013).sin() * (1.0 + (i % 7) as f32 * 0.1)).collect();
+        // Kein Stück ist ein Vielfaches der Chunk-Größe, manche sind kürzer als ein Chunk oder ein Frame
+        let lengths = [1, 3, 700, 5, 2047, 2, 129, 4099];
+        for channels in [1, 2, 3] {
+            for mode in [WaveformMode::Peak, WaveformMode::Rms] {
+                for downmix in [false, true] {
+                    let mut whole = Vec::new();
+                    reduce_chunks(&signal, channels, 256, mode, downmix, &mut Vec::new(), |point| whole.push(point));
+                    let mut remainder = Vec::new();
+                    let mut pieces = Vec::new();
+                    for piece in split_by(&signal, &lengths) {
+                        reduce_chunks(piece, channels, 256, mode, downmix, &mut remainder, |point| pieces.push(point));
+                    }
+                    assert_eq!(pieces, whole, "{} channels, {:?}, downmix {}", channels, mode, downmix);
+                    assert_eq!(remainder.len(), signal.len() % aligned_chunk_size(256, channels));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reduce_chunks_waits_for_a_full_chunk() {
+        let mut remainder = Vec::new();
+        let mut points = Vec::new();
+        // Jeder Aufruf liefert weniger als einen Chunk, erst der dritte macht ihn voll
+        reduce_chunks(&[0.1, -0.5, 0.2], 1, 8, WaveformMode::Peak, false, &mut remainder, |point| points.push(point));
+        reduce_chunks(&[0.3, 0.4], 1, 8, WaveformMode::Peak, false, &mut remainder, |point| points.push(point));
+        assert!(points.is_empty());
+        assert_eq!(remainder, [0.1, -0.5, 0.2, 0.3, 0.4]);
+        reduce_chunks(&[0.9, 0.0, -0.1, 0.7], 1, 8, WaveformMode::Peak, false, &mut remainder, |point| points.push(point));
+        assert_eq!(points.len(), 1);
+        assert_eq!((points[0].min[0], points[0].max[0]), (-0.5, 0.9));
+        assert_eq!(remainder, [0.7]);
+    }
+
+    #[test]
+    fn process_audio_in_pieces_matches_the_whole_signal() {
+        let signal: Vec<f32> = (0..20_000).map(|i| 0.2 + (i as f32 * 0.021).sin() * if (i / 3000) % 2 == 0 { 0.8 } else { 0.001 }).collect();
+        let gate = Some(GateSettings::new(-40.0, Duration::from_millis(5), Duration::from_millis(10), 48000));
+        let envelope = Some(EnvelopeSettings::new(Duration::from_millis(1), Duration::from_millis(20), 48000));
+        let run = |pieces: Vec<&[f32]>| {
+            let mut state = ProcessState::default();
+            let mut points = Vec::new();
+            for piece in pieces {
+                process_audio(piece, 2, 512, WaveformMode::Peak, false, 0, Some(DcBlocker::pole(10.0, 48000)), Some(48000), gate, envelope, &mut state, |point| points.push(point));
+            }
+            points
+        };
+        // Filter, Gate und Hüllkurve behalten ihren Zustand je Kanal auch über Stücke, die mitten im Frame enden
+        let whole = run(vec![&signal]);
+        assert_eq!(whole.len(), signal.len() / 512);
+        assert_eq!(run(split_by(&signal, &[1, 511, 3, 1000, 77])), whole);
+    }
+
+    #[test]
+    fn reduce_chunks_rounds_chunk_size_to_whole_frames() {
+        let mut remainder = Vec::new();
+        let mut points = Vec::new();
+        // Chunk-Größe 5 bei Stereo wird zu 4, damit Kanal 0 immer links bleibt
+        reduce_chunks(&[0.1, 0.9, 0.2, 0.8, 0.3, 0.7], 2, 5, WaveformMode::Rms, false, &mut remainder, |point| points.push(point));
+        assert_eq!(points.len(), 1);
+        assert!((points[0].level(0) - ((0.01f32 + 0.04) / 2.0).sqrt()).abs() < 1e-6);
+        assert!((points[0].level(1) - ((0.81f32 + 0.64) / 2.0).sqrt()).abs() < 1e-6);
+        assert_eq!(remainder, [0.3, 0.7]);
+    }
+
+    #[test]
+    fn stereo_correlation_detects_phase() {
+        let sine: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.05).sin()).collect();
+        let interleave = |right: &dyn Fn(f32) -> f32| sine.iter().flat_map(|&s| [s, right(s)]).collect::<Vec<f32>>();
+        assert!((stereo_correlation(&interleave(&|s| s * 0.5), 2).unwrap() - 1.0).abs() < 1e-5);
+        assert!((stereo_correlation(&interleave(&|s| -s), 2).unwrap() + 1.0).abs() < 1e-5);
+        // Sinus gegen Kosinus derselben Frequenz ist über ganze Perioden unkorreliert
+        let quadrature: Vec<f32> = (0..1257).flat_map(|i| [(i as f32 * 0.05).sin(), (i as f32 * 0.05).cos()]).collect();
+        assert!(stereo_correlation(&quadrature, 2).unwrap().abs() < 0.01);
+        assert_eq!(stereo_correlation(&interleave(&|_| 0.0), 2), None);
+        assert_eq!(stereo_correlation(&sine, 1), None);
+    }
+
+    #[test]
+    fn downmix_averages_over_all_channels() {
+        // Drei Kanäle mit 0.9, -0.3, 0.0: Mono-Summe 0.2, der Peak je Kanal wäre 0.9
+        let chunk: Vec<f32> = (0..CHUNK_SIZE / 3 * 3).map(|i| [0.9, -0.3, 0.0][i % 3]).collect();
+        let point = reduce_chunk(&chunk, 3, WaveformMode::Peak, true);
+        for value in &point.levels()[..3] {
+            assert!((value - 0.2).abs() < 1e-6, "{}", value);
+        }
+        assert_eq!(point.level(3), 0.0);
+        assert!((reduce_chunk(&chunk, 3, WaveformMode::Peak, false).level(0) - 0.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn dc_filter_removes_offset_per_channel() {
+        // Kanal 0 mit +0.5 Offset, Kanal 1 mit -0.25 Offset, beide mit einem kleinen Sinus
+        let data: Vec<f32> = (0..48000 * 2).map(|i| (i as f32 * 0.01).sin() * 0.1 + if i % 2 == 0 { 0.5 } else { -0.25 }).collect();
+        let mut points = Vec::new();
+        let mut state = ProcessState::default();
+        let pole = DcBlocker::pole(10.0, 48000);
+        for buffer in data.chunks(960) {
+            process_audio(buffer, 2, CHUNK_SIZE, WaveformMode::Peak, false, 0, Some(pole), None, None, None, &mut state, |point| points.push(point));
+        }
+        // Nach dem Einschwingen bleibt je Kanal nur der Sinus übrig
+        let last = points.last().unwrap();
+        assert!(last.level(0) < 0.15, "{}", last.level(0));
+        assert!(last.level(1) < 0.15, "{}", last.level(1));
+    }
+
+    #[test]
+    fn odd_length_buffers_keep_channels_aligned() {
+        // Drei Kanäle mit festen Pegeln, Chunk-Größe und Puffer sind keine Vielfachen der Kanalzahl
+        let levels = [0.1, 0.2, 0.3];
+        let data: Vec<f32> = (0..3 * 10_000).map(|i| levels[i % 3]).collect();
+        let mut points = Vec::new();
+        let mut state = ProcessState::default();
+        let mut offset = 0;
+        for len in [7, 13, 5, 1, 128, 1000, 2, 11].iter().cycle() {
+            if offset >= data.len() {
+                break;
+            }
+            let end = (offset + len).min(data.len());
+            process_audio(&data[offset..end], 3, 128, WaveformMode::Peak, false, 0, None, None, None, None, &mut state, |point| points.push(point));
+            offset = end;
+        }
+        assert_eq!(points.len(), data.len() / 126);
+        for point in &points {
+            assert_eq!(&point.levels()[..3], &levels);
+        }
+    }
+
+    #[test]
+    fn reset_keeps_a_started_frame() {
+        // Nach 7 Samples endet der Rest mitten im dritten Frame
+        let levels = [0.1, 0.2, 0.3];
+        let data: Vec<f32> = (0..3 * 1000).map(|i| levels[i % 3]).collect();
+        let mut points = Vec::new();
+        let mut state = ProcessState::default();
+        process_audio(&data[..7], 3, 128, WaveformMode::Peak, false, 0, None, None, None, None, &mut state, |point| points.push(point));
+        state.reset(3);
+        process_audio(&data[7..500], 3, 128, WaveformMode::Peak, false, 0, None, None, None, None, &mut state, |point| points.push(point));
+        // Auch ein Wechsel der Chunk-Größe verwirft nur ganze Frames
+        process_audio(&data[500..], 3, 64, WaveformMode::Peak, false, 0, None, None, None, None, &mut state, |point| points.push(point));
+        assert_eq!(points.len(), 3 + 39);
+        for point in &points {
+            assert_eq!(&point.levels()[..3], &levels);
+        }
+    }
+
+    #[test]
+    fn full_scale_samples_are_reported_as_clipping() {
+        let mut state = ProcessState::default();
+        assert!(!process_audio(&[0.5f32, -0.99], 2, CHUNK_SIZE, WaveformMode::Peak, false, 0, None, None, None, None, &mut state, |_| {}));
+        assert!(process_audio(&[0.5f32, -1.0], 2, CHUNK_SIZE, WaveformMode::Peak, false, 0, None, None, None, None, &mut state, |_| {}));
+        assert!(!process_audio(&[i16::MAX - 1, i16::MIN + 1], 2, CHUNK_SIZE, WaveformMode::Peak, false, 0, None, None, None, None, &mut state, |_| {}));
+        assert!(process_audio(&[0i16, i16::MIN], 2, CHUNK_SIZE, WaveformMode::Peak, false, 0, None, None, None, None, &mut state, |_| {}));
+    }
+
+    #[test]
+    fn i16_input_matches_equivalent_f32_input() {
+        let ints: Vec<i16> = (0..CHUNK_SIZE).map(|i| ((i as f32 * 0.05).sin() * 16384.0) as i16).collect();
+        let floats: Vec<f32> = ints.iter().map(|&s| s as f32 / 32768.0).collect();
+        let mut int_points = Vec::new();
+        process_audio(&ints, 1, CHUNK_SIZE, WaveformMode::Peak, false, 0, None, None, None, None, &mut ProcessState::default(), |point| int_points.push(point));
+        let int_point = int_points[0];
+        let float_point = reduce(&floats, 1, WaveformMode::Peak)[0];
+        assert!(int_point.level(0) <= 1.0);
+        assert!((int_point.min[0] - float_point.min[0]).abs() < 1e-6);
+        assert!((int_point.max[0] - float_point.max[0]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn integer_formats_are_normalized_to_full_scale() {
+        assert_eq!(i8::MIN.normalize(), -1.0);
+        assert_eq!(i16::MIN.normalize(), -1.0);
+        assert_eq!(i32::MIN.normalize(), -1.0);
+        assert_eq!(0u16.normalize(), -1.0);
+        assert_eq!(32768u16.normalize(), 0.0);
+        assert!((i16::MAX.normalize() - 1.0).abs() < 1e-4);
+        assert!((u16::MAX.normalize() - 1.0).abs() < 1e-4);
+        assert!((i32::MAX.normalize() - 1.0).abs() < 1e-6);
+        assert_eq!(0.5f32.normalize(), 0.5);
+    }
+
+    #[test]
+    fn full_scale_table_maps_formats_and_padded_samples_to_unity() {
+        assert_eq!(i16::full_scale(None), 32768.0);
+        assert_eq!(i16::MIN.normalize_to(i16::full_scale(None)), -1.0);
+        assert!((i16::MAX.normalize_to(i16::full_scale(None)) - 1.0).abs() < 1e-4);
+        assert_eq!(i32::full_scale(None), 2147483648.0);
+        assert_eq!(i32::MIN.normalize_to(i32::full_scale(None)), -1.0);
+        assert!((i32::MAX.normalize_to(i32::full_scale(None)) - 1.0).abs() < 1e-6);
+        // Angaben, die das Format nicht unterschreiten, und Gleitkomma bleiben beim Tabellenwert
+        assert_eq!(i16::full_scale(Some(16)), 32768.0);
+        assert_eq!(i16::full_scale(Some(24)), 32768.0);
+        assert_eq!(f32::full_scale(Some(24)), 1.0);
+
+        // 24 Bit rechtsbündig in 32-Bit-Containern
+        let padded = i32::full_scale(Some(24));
+        assert_eq!(padded, 8388608.0);
+        assert_eq!((-8388608i32).normalize_to(padded), -1.0);
+        assert!((8388607i32.normalize_to(padded) - 1.0).abs() < 1e-6);
+        assert!(8388607i32.is_full_scale_of(padded) && (-8388608i32).is_full_scale_of(padded));
+        assert!(!8388606i32.is_full_scale_of(padded));
+        assert!(8388607i32.normalize() < 0.01);
+
+        let mut state = ProcessState::default();
+        state.set_significant_bits(Some(24));
+        let mut points = Vec::new();
+        let data = [4194304i32, -8388608].repeat(CHUNK_SIZE / 2);
+        let clipped = process_audio(&data, 2, CHUNK_SIZE, WaveformMode::Peak, false, 0, None, None, None, None, &mut state, |point| points.push(point));
+        assert!(clipped);
+        assert_eq!(points[0].max[0], 0.5);
+        assert_eq!(points[0].min[1], -1.0);
+    }
+
+    #[test]
+    fn noise_gate_silences_quiet_chunks_per_channel() {
+        let gate = Some(GateSettings::new(-40.0, Duration::ZERO, Duration::ZERO, 48000));
+        // Links Rauschen unter der Schwelle, rechts ein Signal darüber
+        let data: Vec<f32> = (0..CHUNK_SIZE / 2).flat_map(|i| [if i % 2 == 0 { 0.001 } else { -0.001 }, 0.5]).collect();
+        let mut state = ProcessState::default();
+        let mut points = Vec::new();
+        process_audio(&data, 2, CHUNK_SIZE, WaveformMode::Peak, false, 0, None, None, gate, None, &mut state, |point| points.push(point));
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].levels()[..2], [0.0, 0.5]);
+        assert!(state.gate_open());
+    }
+
+    #[test]
+    fn inverted_channels_flip_their_phase_before_reduction() {
+        let data: Vec<f32> = (0..256).flat_map(|_| [0.5, 0.25, 0.1]).collect();
+        let run = |pieces: Vec<&[f32]>, inverted: u32| {
+            let mut state = ProcessState::default();
+            let mut points = Vec::new();
+            for piece in pieces {
+                process_audio(piece, 3, 384, WaveformMode::Peak, false, inverted, None, None, None, None, &mut state, |point| points.push(point));
+            }
+            points
+        };
+        let points = run(vec![&data], 0b010);
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].min[..3], [0.5, -0.25, 0.1]);
+        assert_eq!(points[0].max[..3], [0.5, -0.25, 0.1]);
+        // Auch Stücke, die mitten im Frame enden, drehen den richtigen Kanal
+        assert_eq!(run(split_by(&data, &[2, 7, 100]), 0b010), points);
+        assert_eq!(run(vec![&data], 0)[0].max[..3], [0.5, 0.25, 0.1]);
+    }
+
+    #[test]
+    fn resampled_input_keeps_channels_aligned() {
+        // Drei Kanäle mit festen Pegeln, heruntergetaktet: jeder Wert bleibt bei seinem Kanal
+        let levels = [0.1, 0.2, 0.3];
+        let data: Vec<f32> = (0..3 * 9600).map(|i| levels[i % 3]).collect();
+        let mut state = ProcessState::resampling(96000, 48000, 3);
+        let mut points = Vec::new();
+        for buffer in data.chunks(1001) {
+            process_audio(buffer, 3, 128, WaveformMode::Peak, false, 0, None, None, None, None, &mut state, |point| points.push(point));
+        }
+        assert!(points.len() > 100, "{}", points.len());
+        // Der erste Punkt enthält noch das Einschwingen aus der Stille
+        for point in &points[1..] {
+            assert!(point.levels()[..3].iter().zip(levels).all(|(value, level)| (value - level).abs() < 1e-4), "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn asymmetric_input_keeps_its_signed_envelope() {
+        // Sinus mit Offset: -0.2..0.8 statt symmetrisch um 0; Kanal 1 liegt ganz über 0
+        let data: Vec<f32> = (0..CHUNK_SIZE / 2)
+            .flat_map(|i| {
+                let sine = (2.0 * std::f32::consts::PI * 8.0 * i as f32 / (CHUNK_SIZE / 2) as f32).sin();
+                [0.3 + 0.5 * sine, 0.5 + 0.1 * sine]
+            })
+            .collect();
+        let point = reduce(&data, 2, WaveformMode::Peak)[0];
+        assert!((point.min[0] + 0.2).abs() < 1e-4 && (point.max[0] - 0.8).abs() < 1e-4, "{:?}", point);
+        assert!((point.min[1] - 0.4).abs() < 1e-4 && (point.max[1] - 0.6).abs() < 1e-4, "{:?}", point);
+        // Der Pegel bleibt die größte Abweichung von 0
+        assert!((point.level(0) - 0.8).abs() < 1e-4);
+        assert!((point.level(1) - 0.6).abs() < 1e-4);
+    }
+
+    #[test]
+    fn correlation_matrix_covers_every_pair() {
+        // Kanal 1 gleichphasig, Kanal 2 gegenphasig zu Kanal 0, Kanal 3 still
+        let data: Vec<f32> = (0..480)
+            .flat_map(|i| {
+                let s = (i as f32 * 0.1).sin();
+                [s, 0.5 * s, -s, 0.0]
+            })
+            .collect();
+        let matrix = correlation_matrix(&data, 4);
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+        assert!(close(matrix[0][0], 1.0) && close(matrix[0][1], 1.0) && close(matrix[0][2], -1.0) && close(matrix[1][2], -1.0));
+        assert_eq!(matrix[2][0], matrix[0][2]);
+        // Der stille Kanal und die fehlenden Kanäle haben keinen Wert, ohne die übrigen zu stören
+        assert!(matrix[3].iter().chain(matrix.iter().map(|row| &row[3])).all(|c| c.is_nan()));
+        assert!(matrix[4][0].is_nan() && matrix[0][MAX_CHANNELS - 1].is_nan());
+    }
+}
@@ -0,0 +1,221 @@
+// Lautheit nach ITU-R BS.1770 / EBU R 128: K-bewertete mittlere Leistung über 400 ms (Momentary)
+// und 3 s (Short-term) in LUFS, dazu die gegatete integrierte Lautheit seit dem letzten Reset.
+// Läuft im Audio-Thread, die Gleitfenster bestehen daher über die Puffergrenzen hinweg.
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::biquad::Biquad;
+
+/// Teilblöcke von 100 ms, aus denen sich die Fenster zusammensetzen. Ein 400-ms-Block je
+/// Teilblock ergibt die 75 % Überlappung, die BS.1770 für das Gating vorsieht.
+const STEP_SECONDS: f64 = 0.1;
+/// Teilblöcke je Momentary-Fenster (400 ms)
+const MOMENTARY_STEPS: usize = 4;
+/// Teilblöcke je Short-term-Fenster (3 s)
+const SHORT_TERM_STEPS: usize = 30;
+
+/// Absolutes Gate: leisere Blöcke zählen nicht zur integrierten Lautheit
+pub const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Relatives Gate unter der vorläufigen integrierten Lautheit
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// Lautheit in LUFS zur mittleren Leistung `power` (gewichtete Summe der Kanäle).
+fn lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// K-Bewertung: Höhenanhebung (Kopfmodell) und RLB-Hochpass, Koeffizienten für beliebige
+/// Abtastraten wie in BS.1770 Anhang 1 (bei 48 kHz die dort tabellierten Werte).
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate.max(1) as f64;
+    let k = (PI * 1_681.974_450_955_533 / fs).tan();
+    let q = 0.707_175_236_955_419_6;
+    let vh = 10f64.powf(3.999_843_853_973_347 / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let shelf = Biquad::new([vh + vb * k / q + k * k, 2.0 * (k * k - vh), vh - vb * k / q + k * k], [1.0 + k / q + k * k, 2.0 * (k * k - 1.0), 1.0 - k / q + k * k]);
+    let k = (PI * 38.135_470_876_024_44 / fs).tan();
+    let q = 0.500_327_037_323_877_3;
+    let a0 = 1.0 + k / q + k * k;
+    // Der Zähler bleibt unnormiert, wie in der Norm
+    let high_pass = Biquad::new([a0, -2.0 * a0, a0], [a0, 2.0 * (k * k - 1.0), 1.0 - k / q + k * k]);
+    [shelf, high_pass]
+}
+
+/// Gewicht eines Kanals. Bei 5.1 (L, R, C, LFE, Ls, Rs) zählt LFE nicht und die
+/// Surround-Kanäle zählen +1,5 dB, sonst zählen alle Kanäle gleich.
+fn channel_weight(channel: usize, channels: usize) -> f64 {
+    match (channels, channel) {
+        (6, 3) => 0.0,
+        (6, 4 | 5) => 1.41,
+        _ => 1.0,
+    }
+}
+
+#[derive(Default)]
+pub struct LoudnessMeter {
+    filters: [Biquad; 2],
+    /// Abtastrate, für die `filters` berechnet sind, 0 = noch nicht berechnet
+    sample_rate: u32,
+    /// Zustand beider Filterstufen je Kanal
+    state: Vec<[[f32; 2]; 2]>,
+    /// Nächster Kanal im interleavten Strom
+    channel: usize,
+    /// Gewichtete Leistung des laufenden Teilblocks
+    frame_power: f64,
+    block_power: f64,
+    block_frames: usize,
+    /// Mittlere Leistung der letzten `SHORT_TERM_STEPS` Teilblöcke, der neueste hinten
+    steps: VecDeque<f64>,
+    /// Leistung aller 400-ms-Blöcke über dem absoluten Gate, für die integrierte Lautheit
+    gated_blocks: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Nimmt interleavte Samples (±1.0) auf, die mit Kanal 0 beginnen. Ändern sich Kanalzahl
+    /// oder Abtastrate, beginnt die Messung von vorn.
+    pub fn process(&mut self, samples: impl IntoIterator<Item = f32>, channels: usize, sample_rate: u32) {
+        let channels = channels.max(1);
+        if self.sample_rate != sample_rate || self.state.len() != channels {
+            self.reset();
+            self.filters = k_weighting(sample_rate);
+            self.sample_rate = sample_rate;
+            self.state = vec![[[0.0; 2]; 2]; channels];
+        }
+        let step_frames = ((sample_rate as f64 * STEP_SECONDS).round() as usize).max(1);
+        for sample in samples {
+            let state = &mut self.state[self.channel];
+            let filtered = self.filters[1].process(self.filters[0].process(sample, &mut state[0]), &mut state[1]) as f64;
+            self.frame_power += channel_weight(self.channel, channels) * filtered * filtered;
+            self.channel += 1;
+            if self.channel < channels {
+                continue;
+            }
+            self.channel = 0;
+            self.block_power += std::mem::take(&mut self.frame_power);
+            self.block_frames += 1;
+            if self.block_frames == step_frames {
+                self.finish_step(step_frames);
+            }
+        }
+    }
+
+    fn finish_step(&mut self, step_frames: usize) {
+        if self.steps.len() == SHORT_TERM_STEPS {
+            self.steps.pop_front();
+        }
+        self.steps.push_back(std::mem::take(&mut self.block_power) / step_frames as f64);
+        self.block_frames = 0;
+        if let Some(power) = self.window_power(MOMENTARY_STEPS) {
+            if lufs(power) > ABSOLUTE_GATE_LUFS {
+                self.gated_blocks.push(power);
+            }
+        }
+    }
+
+    /// Mittlere Leistung der letzten `steps` Teilblöcke, `None` solange es weniger sind.
+    fn window_power(&self, steps: usize) -> Option<f64> {
+        (self.steps.len() >= steps).then(|| self.steps.iter().rev().take(steps).sum::<f64>() / steps as f64)
+    }
+
+    /// Momentary-Lautheit (400 ms) in LUFS, `None` bis das Fenster gefüllt ist oder bei Stille.
+    pub fn momentary(&self) -> Option<f64> {
+        self.window_power(MOMENTARY_STEPS).filter(|&power| power > 0.0).map(lufs)
+    }
+
+    /// Short-term-Lautheit (3 s) in LUFS, `None` bis das Fenster gefüllt ist oder bei Stille.
+    pub fn short_term(&self) -> Option<f64> {
+        self.window_power(SHORT_TERM_STEPS).filter(|&power| power > 0.0).map(lufs)
+    }
+
+    /// Integrierte Lautheit seit dem letzten Reset mit absolutem und relativem Gate,
+    /// `None` solange kein Block über dem absoluten Gate lag.
+    pub fn integrated(&self) -> Option<f64> {
+        if self.gated_blocks.is_empty() {
+            return None;
+        }
+        let ungated = self.gated_blocks.iter().sum::<f64>() / self.gated_blocks.len() as f64;
+        let relative_gate = lufs(ungated) + RELATIVE_GATE_LU;
+        let (sum, count) = self.gated_blocks.iter().filter(|&&power| lufs(power) > relative_gate).fold((0.0, 0usize), |(sum, count), power| (sum + power, count + 1));
+        (count > 0).then(|| lufs(sum / count as f64))
+    }
+
+    /// Verwirft Filterzustand, Fenster und die integrierte Messung.
+    pub fn reset(&mut self) {
+        self.state.fill([[0.0; 2]; 2]);
+        self.channel = 0;
+        self.frame_power = 0.0;
+        self.block_power = 0.0;
+        self.block_frames = 0;
+        self.steps.clear();
+        self.gated_blocks.clear();
+    }
+}
+
+/// Messwerte für die UI, vom Audio-Thread je Puffer geschrieben. Jeder Wert ist für sich
+/// gültig, daher genügt `Relaxed` wie bei `Params`.
+pub struct LoudnessReadout {
+    momentary: AtomicU32,
+    short_term: AtomicU32,
+    integrated: AtomicU32,
+}
+
+impl LoudnessReadout {
+    pub fn new() -> Self {
+        let none = f32::NEG_INFINITY.to_bits();
+        LoudnessReadout { momentary: AtomicU32::new(none), short_term: AtomicU32::new(none), integrated: AtomicU32::new(none) }
+    }
+
+    pub fn store(&self, meter: &LoudnessMeter) {
+        let bits = |value: Option<f64>| value.map_or(f32::NEG_INFINITY, |value| value as f32).to_bits();
+        self.momentary.store(bits(meter.momentary()), Ordering::Relaxed);
+        self.short_term.store(bits(meter.short_term()), Ordering::Relaxed);
+        self.integrated.store(bits(meter.integrated()), Ordering::Relaxed);
+    }
+
+    /// (Momentary, Short-term, Integrated) in LUFS, `-inf` ohne Messwert.
+    pub fn load(&self) -> (f32, f32, f32) {
+        let value = |atomic: &AtomicU32| f32::from_bits(atomic.load(Ordering::Relaxed));
+        (value(&self.momentary), value(&self.short_term), value(&self.integrated))
+    }
+}
+
+impl Default for LoudnessReadout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loudness_matches_bs1770_reference() {
+        let sample_rate = 48_000;
+        // 997 Hz mit -20 dBFS Spitze ergibt nach BS.1770 -23,01 LUFS
+        let sine: Vec<f32> = (0..3 * sample_rate).map(|n| 0.1 * (2.0 * std::f32::consts::PI * 997.0 * n as f32 / sample_rate as f32).sin()).collect();
+        let mut meter = LoudnessMeter::new();
+        meter.process(sine[..14_400].iter().copied(), 1, sample_rate as u32);
+        assert_eq!(meter.momentary(), None);
+        // In Puffern wie vom Audio-Thread, die Fenster laufen über die Grenzen hinweg
+        for chunk in sine[14_400..].chunks(512) {
+            meter.process(chunk.iter().copied(), 1, sample_rate as u32);
+        }
+        for value in [meter.momentary(), meter.short_term(), meter.integrated()] {
+            assert!((value.unwrap() + 23.01).abs() < 0.05, "{:?}", value);
+        }
+
+        // Stille fällt unter das absolute Gate und zieht die integrierte Lautheit nicht mit
+        meter.process(std::iter::repeat_n(0.0, 3 * sample_rate), 1, sample_rate as u32);
+        assert!(meter.momentary().is_none_or(|lufs| lufs < ABSOLUTE_GATE_LUFS));
+        assert!((meter.integrated().unwrap() + 23.23).abs() < 0.05);
+        meter.reset();
+        assert_eq!(meter.integrated(), None);
+    }
+}
@@ -1,173 +1,2302 @@
 // Prevent console window in addition to Slint window in Windows release builds when, e.g., starting the app via file manager. Ignored on other platforms.
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::collections::VecDeque;
 use std::error::Error;
-use std::sync::{Arc, Mutex};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{SampleRate, Stream};
-use slint::SharedVector;
-use num_traits::ToPrimitive;
-use std::cell::RefCell;
+use std::sync::Arc;
+use clap::Parser;
+use rtrb::Consumer;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+mod auto_scale;
+mod calibration;
+mod clip_trigger;
+mod colormap;
+mod correlation_view;
+mod csv_export;
+mod headless;
+mod histogram;
+mod onset;
+mod peak_hold;
+mod point_queue;
+mod render_plot;
+mod ring_buffer;
+mod settings;
+mod signal_detect;
+mod smoothing;
+mod spectrogram;
+mod trigger;
+mod vu_meter;
+mod windowing;
+
+use auto_scale::AutoScale;
+use calibration::Calibration;
+use clip_trigger::ClipTrigger;
+use colormap::Colormap;
+use peak_hold::PeakHold;
+use point_queue::{point_queue, PointReceiver};
+use render_plot::{AmplitudeScale, Envelope, PlotLayout, PlotOptions, TimeAxis, TraceFill, TracePoint, LANE_FULL_SCALE};
+use ring_buffer::RingBuffer;
+use rmnc::audio_error::AudioError;
+use rmnc::capture::DEFAULT_BUFFER_DURATION;
+use rmnc::envelope::EnvelopePreset;
+use rmnc::loudness;
+use rmnc::params::Params;
+use rmnc::recorder::Recorder;
+use rmnc::wav_file::WavFile;
+use rmnc::{list_input_devices, list_loopback_devices, AudioConfig, Meters, StreamInfo, WaveformCapture, WaveformMode, WaveformPoint, MAX_CHANNELS};
+use settings::Settings;
+use signal_detect::SignalDetector;
+use smoothing::Smoothing;
+use spectrogram::Spectrogram;
+use trigger::{Edge, Scope, TriggerMode, TriggerSettings};
+use vu_meter::VuMeter;
+use windowing::WindowKind;
+
+use slint::winit_030::{winit, EventResult, WinitWindowAccessor};
 
 slint::include_modules!();
 
+/// Anzahl interleavter Samples (über alle Kanäle), die zu einem Punkt zusammengefasst werden.
+/// Ein Chunk entspricht `chunk_size / channels / sample_rate` Sekunden, bei Stereo mit 48 kHz
+/// also 2048 / 2 / 48000 ≈ 21,3 ms.
+const DEFAULT_CHUNK_SIZE: usize = 2048;
+
+/// Kapazität der Queue zwischen Audio-Thread und UI in Punkten, höchstens so viele Punkte sind
+/// unterwegs. Reicht bei kleinster Chunk-Größe für mehrere UI-Frames, ist die Queue voll, verdrängt
+/// jeder neue Punkt den ältesten (siehe `PointSender::push`), gezählt in `Meters::dropped_points`.
+const POINT_QUEUE_CAPACITY: usize = 4096;
+
+/// Kapazität der Queue für Mono-Samples zu Spektrogramm und Oszilloskop, reicht für gut eine Sekunde bei 48 kHz
+const SAMPLE_QUEUE_CAPACITY: usize = 1 << 16;
+
+/// Standard-FFT-Größe des Spektrogramms
+const DEFAULT_FFT_SIZE: usize = 1024;
+
+/// Höhe des Plots in Pixeln, entspricht dem Plot-Bereich in `app-window.slint`. Die Breite
+/// folgt dem Fenster (`plot-columns`) und wird auf `MAX_PLOT_WIDTH` begrenzt.
+const PLOT_HEIGHT: u32 = 200;
+const MAX_PLOT_WIDTH: u32 = 4096;
+
+/// Pegel der Gitterlinien im gerasterten Plot in dBFS
+const GRID_LEVELS_DB: [f32; 4] = [0.0, -6.0, -12.0, -24.0];
+
+/// Intervall des Render-Timers und erlaubter Bereich für die Einstellung in der UI
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_millis(50);
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_millis(10);
+const MAX_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Erlaubte Kantenlänge beim Bildexport in Pixeln
+const MIN_EXPORT_SIZE: i32 = 100;
+const MAX_EXPORT_SIZE: i32 = 16384;
+
+/// Anzeigedauer einer Meldung über dem Fenster
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// Untergrenze der Pegelanzeige in dBFS, gilt auch für Stille
+const MIN_LEVEL_DB: f32 = -120.0;
+
+/// Bereich des Reglers für die feste Verstärkung in dB
+const MIN_GAIN_DB: f32 = -24.0;
+const MAX_GAIN_DB: f32 = 48.0;
+
+/// Größte Chunk-Größe, die auf der Kommandozeile angenommen wird
+const MAX_CHUNK_SIZE: usize = 1 << 16;
+
+/// Aufnahmedauer ohne Fenster, wenn `--duration` fehlt
+const DEFAULT_HEADLESS_DURATION: Duration = Duration::from_secs(10);
+
+/// Abstand, in dem geänderte Einstellungen gespeichert werden
+const SETTINGS_SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Standardlänge des sichtbaren Verlaufs in Punkten
+const DEFAULT_HISTORY_LEN: usize = 1000;
+
+/// Wartezeit vor dem ersten Versuch, einen verlorenen Stream neu zu öffnen; sie verdoppelt sich
+/// mit jedem Fehlversuch bis `RECONNECT_MAX_DELAY`
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_millis(250);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(8);
+
+/// Anzahl gehaltener Standbilder, bei einem weiteren wird das älteste verworfen
+const MAX_FROZEN_FRAMES: usize = 8;
+
+/// Kommandozeile. Die Doc-Kommentare der Felder erscheinen in `--help`.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Input device name or index as listed at startup
+    #[arg(long)]
+    device: Option<String>,
+    /// Requested sample rate in Hz; falls back to the device default if unsupported
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    sample_rate: Option<u32>,
+    /// Capture at the device's native rate and resample to this rate in Hz before reduction
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    resample: Option<u32>,
+    /// Requested audio buffer duration in milliseconds, clamped to the device's supported range [default: last used, 20]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    buffer_ms: Option<u32>,
+    /// Significant bits of padded integer formats, e.g. 24 for 24-bit samples in 32-bit containers; normalizes to this width instead of the container
+    #[arg(long, value_parser = clap::value_parser!(u32).range(2..=32))]
+    significant_bits: Option<u32>,
+    /// Interleaved samples reduced to one point [default: last used, 2048 for --headless]
+    #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=MAX_CHUNK_SIZE as u64))]
+    chunk_size: Option<usize>,
+    /// Capture duration in seconds; the window closes afterwards [default for --headless: 10]
+    #[arg(long, value_parser = parse_seconds)]
+    duration: Option<f64>,
+    /// Capture without opening a window and write the reduced points as CSV
+    #[arg(long)]
+    headless: bool,
+    /// CSV file to write instead of stdout
+    #[arg(long, requires = "headless")]
+    output: Option<PathBuf>,
+}
+
+/// Dauer in Sekunden, muss endlich und größer als 0 sein.
+fn parse_seconds(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(seconds) if seconds.is_finite() && seconds > 0.0 => Ok(seconds),
+        Ok(_) => Err("must be a positive number of seconds".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    let sample_rate = cli.sample_rate.unwrap_or(AudioConfig::default().sample_rate);
+    // Ohne Fenster bleiben die gespeicherten Einstellungen unberührt, damit Skripte reproduzierbar laufen
+    if cli.headless {
+        let buffer_duration = cli.buffer_ms.map_or(DEFAULT_BUFFER_DURATION, |ms| Duration::from_millis(ms as u64));
+        let audio_config = AudioConfig { device: cli.device, loopback: false, sample_rate, resample_to: cli.resample, buffer_duration, significant_bits: cli.significant_bits };
+        let duration = cli.duration.map_or(DEFAULT_HEADLESS_DURATION, Duration::from_secs_f64);
+        return headless::run(&audio_config, cli.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE), duration, cli.output.as_deref());
+    }
+
+    // Angaben auf der Kommandozeile haben Vorrang vor den gespeicherten Einstellungen
+    let settings = Settings::load();
+    let buffer_duration = Duration::from_millis(cli.buffer_ms.unwrap_or(settings.buffer_ms) as u64);
+    let audio_config = AudioConfig { device: cli.device.or_else(|| settings.device.clone()), loopback: settings.loopback, sample_rate, resample_to: cli.resample, buffer_duration, significant_bits: cli.significant_bits };
+
+    for (index, name) in list_input_devices() {
+        println!("Input device {}: {}", index, name);
+    }
+    for (index, name) in list_loopback_devices() {
+        println!("Loopback device {}: {}", index, name);
+    }
+
+    let app = Rc::new(App { settings, sample_rate, resample_to: cli.resample, significant_bits: cli.significant_bits, windows: RefCell::new(Vec::new()), next_id: Cell::new(0) });
+    open_window(&app, audio_config, cli.chunk_size.unwrap_or(app.settings.chunk_size), true)?;
+
+    // Mit --duration schließen sich die Fenster nach der Aufnahmedauer von selbst
+    if let Some(seconds) = cli.duration {
+        slint::Timer::single_shot(Duration::from_secs_f64(seconds), || {
+            let _ = slint::quit_event_loop();
+        });
+    }
+
+    // Läuft, bis das letzte Fenster geschlossen ist
+    slint::run_event_loop()?;
+    // Die Streams werden hier mit ihren Fenstern gedroppt
+    app.windows.borrow_mut().clear();
+    Ok(())
+}
+
+/// Gemeinsamer Zustand aller Fenster: Vorlage für neue Fenster und die offenen Fenster selbst.
+struct App {
+    /// Beim Start geladene Einstellungen, Ausgangspunkt jedes neuen Fensters
+    settings: Settings,
+    sample_rate: u32,
+    resample_to: Option<u32>,
+    significant_bits: Option<u32>,
+    windows: RefCell<Vec<CaptureWindow>>,
+    next_id: Cell<usize>,
+}
+
+/// Ein Fenster mit eigenem Stream, Verlauf und Timer. Mit dem Drop endet sein Stream, die
+/// anderen Fenster laufen weiter.
+struct CaptureWindow {
+    id: usize,
+    _ui: AppWindow,
+    _timer: Rc<slint::Timer>,
+    _settings_timer: Option<slint::Timer>,
+}
+
+/// Öffnet ein Fenster, das `audio_config` mit `chunk_size` aufnimmt, und nimmt es in `app` auf.
+/// Nur das `primary` Fenster speichert seine Einstellungen, damit sich die Fenster dabei nicht
+/// gegenseitig überschreiben.
+fn open_window(app: &Rc<App>, audio_config: AudioConfig, chunk_size: usize, primary: bool) -> Result<(), Box<dyn Error>> {
+    let settings = &app.settings;
+    let id = app.next_id.replace(app.next_id.get() + 1);
     let ui = AppWindow::new()?;
-    let waveform_data = Arc::new(Mutex::new(SharedVector::<(f32, f32)>::default()));
+    apply_settings(&ui, settings);
+    let device_names: Vec<slint::SharedString> = list_input_devices().into_iter().map(|(_, name)| name.into()).collect();
+    ui.set_input_devices(slint::ModelRc::from(device_names.as_slice()));
+    let app_weak = Rc::downgrade(app);
+    ui.on_open_window(move |device| {
+        let Some(app) = app_weak.upgrade() else {
+            return;
+        };
+        let audio_config = AudioConfig {
+            device: Some(device.to_string()),
+            loopback: false,
+            sample_rate: app.sample_rate,
+            resample_to: app.resample_to,
+            buffer_duration: Duration::from_millis(app.settings.buffer_ms as u64),
+            significant_bits: app.significant_bits,
+        };
+        if let Err(err) = open_window(&app, audio_config, app.settings.chunk_size, false) {
+            eprintln!("Error opening window: {}", err);
+        }
+    });
+    // Erst nach dem Schließen entfernen, nicht im Callback des Fensters selbst
+    let app_weak = Rc::downgrade(app);
+    ui.window().on_close_requested(move || {
+        let app_weak = app_weak.clone();
+        slint::Timer::single_shot(Duration::ZERO, move || {
+            if let Some(app) = app_weak.upgrade() {
+                let closed = {
+                    let mut windows = app.windows.borrow_mut();
+                    windows.iter().position(|window| window.id == id).map(|index| windows.remove(index))
+                };
+                drop(closed);
+            }
+        });
+        slint::CloseRequestResponse::HideWindow
+    });
+    // Von der UI gesetzt, vom Audio-Thread je Puffer gelesen
+    let params = Arc::new(Params::new(chunk_size));
+    params.set_dc_cutoff_hz(settings.dc_filter.then_some(settings.dc_cutoff_hz.max(1) as u32));
+    apply_gate(&ui, &params);
+    apply_envelope(&ui, &params);
+    // Übersteuerung, Korrelation und Lautheit, geschrieben vom Audio-Thread und über
+    // Neuverbindungen hinweg von der UI gelesen
+    let meters = Arc::new(Meters::new());
+    let paused = Rc::new(Cell::new(false));
+    // Von "Clear" gesetzt, der Timer leert daraufhin den Verlauf
+    let clear_requested = Rc::new(Cell::new(false));
+    // Von "Freeze" gesetzt, der Timer hält daraufhin ein Standbild des Verlaufs fest
+    let freeze_requested = Rc::new(Cell::new(false));
+    let history_len = Rc::new(Cell::new(settings.history_len.max(1)));
+
+    // Startet einen Stream mit frischen Queues, wird beim ersten Start, bei "Retry" und beim
+    // Umschalten auf Loopback aufgerufen
+    let loopback = Rc::new(Cell::new(audio_config.loopback));
+    let buffer_duration = Rc::new(Cell::new(audio_config.buffer_duration));
+    // Gewähltes Gerät, wechselt über die Geräteauswahl im laufenden Fenster
+    let device = Rc::new(RefCell::new(audio_config.device.clone()));
+    ui.set_buffer_ms(audio_config.buffer_duration.as_millis() as i32);
+    let connect: Rc<dyn Fn() -> Result<Connection, AudioError>> = {
+        let loopback = loopback.clone();
+        let buffer_duration = buffer_duration.clone();
+        let device = device.clone();
+        let params = params.clone();
+        let meters = meters.clone();
+        Rc::new(move || {
+            // Der Audio-Thread schreibt nur in `sender`, die UI liest nur aus `points`
+            let (mut sender, points) = point_queue(POINT_QUEUE_CAPACITY, meters.clone());
+            // Mono-Samples für Spektrogramm und Oszilloskop, nur befüllt solange eines angezeigt wird
+            let (sample_producer, samples) = rtrb::RingBuffer::<f32>::new(SAMPLE_QUEUE_CAPACITY);
+            let audio_config = AudioConfig { device: device.borrow().clone(), loopback: loopback.get(), buffer_duration: buffer_duration.get(), ..audio_config.clone() };
+            let capture = WaveformCapture::start(&audio_config, params.clone(), meters.clone(), Some(sample_producer), move |point| sender.push(point))?;
+            Ok(Connection { capture, points, samples })
+        })
+    };
+
+    // Der Stream muss bis zum Programmende erhalten bleiben, daher außerhalb des Timers speichern.
+    // `None`, solange kein Eingabegerät geöffnet werden konnte.
+    let connection = Rc::new(RefCell::new(None::<Connection>));
+    if let Err(err) = open_connection(&ui, &connection, &*connect) {
+        show_connection_error(&ui, &err);
+    }
+    ui.set_chunk_size(params.chunk_size() as i32);
+    ui.set_history_len(history_len.get() as i32);
+    ui.set_fft_size(DEFAULT_FFT_SIZE as i32);
+    // Geöffnete WAV-Datei, solange sie statt des Live-Eingangs angezeigt wird
+    let wav_file = Rc::new(RefCell::new(None::<WavFile>));
+
+    let ui_weak = ui.as_weak();
+    let wav_file_for_open = wav_file.clone();
+    let connection_for_open = connection.clone();
+    let params_for_open = params.clone();
+    ui.on_open_file(move |path| {
+        let Some(ui) = ui_weak.upgrade() else {
+            return;
+        };
+        let path = PathBuf::from(path.as_str());
+        match WavFile::open(&path, &params_for_open) {
+            Ok(file) => {
+                // Der Live-Stream bleibt geschlossen, solange die Datei angezeigt wird
+                *connection_for_open.borrow_mut() = None;
+                ui.set_recording(false);
+                ui.set_error_message("".into());
+                show_stream_info(&ui, &file.info);
+                ui.set_file_points(file.points().len() as i32);
+                ui.set_file_position(0);
+                ui.set_file_mode(true);
+                ui.set_spectrogram(false);
+                ui.set_trigger(false);
+                ui.set_histogram(false);
+                ui.set_file_status(path.display().to_string().into());
+                *wav_file_for_open.borrow_mut() = Some(file);
+            }
+            Err(err) => {
+                eprintln!("Error opening {}: {}", path.display(), err);
+                ui.set_file_status(format!("Cannot open {}: {}", path.display(), err).into());
+            }
+        }
+    });
+
+    // Auf das Fenster gezogene WAV-Dateien wie über "Open" öffnen
+    let ui_weak = ui.as_weak();
+    let toast_timer = Rc::new(slint::Timer::default());
+    ui.window().on_winit_window_event(move |_, event| {
+        let winit::event::WindowEvent::DroppedFile(path) = event else {
+            return EventResult::Propagate;
+        };
+        let Some(ui) = ui_weak.upgrade() else {
+            return EventResult::Propagate;
+        };
+        match check_dropped_file(path) {
+            Ok(()) => {
+                let path = path.display().to_string();
+                ui.set_file_path(path.clone().into());
+                ui.invoke_open_file(path.into());
+            }
+            Err(message) => show_toast(&ui, &toast_timer, message),
+        }
+        EventResult::PreventDefault
+    });
+
+    let ui_weak = ui.as_weak();
+    let wav_file_for_live = wav_file.clone();
+    let connection_for_live = connection.clone();
+    let paused_for_live = paused.clone();
+    let connect_for_live = connect.clone();
+    ui.on_go_live(move || {
+        let Some(ui) = ui_weak.upgrade() else {
+            return;
+        };
+        *wav_file_for_live.borrow_mut() = None;
+        ui.set_file_mode(false);
+        ui.set_file_status("".into());
+        match open_connection(&ui, &connection_for_live, &*connect_for_live) {
+            Ok(()) => {
+                paused_for_live.set(false);
+                ui.set_paused(false);
+            }
+            Err(err) => show_connection_error(&ui, &err),
+        }
+    });
+
+    // Zuletzt angezeigter Verlauf für "Save Image"
+    let snapshot = Rc::new(RefCell::new(None::<PlotSnapshot>));
+    let snapshot_for_timer = snapshot.clone();
+    let ui_weak = ui.as_weak();
+    ui.on_save_image(move || {
+        let Some(ui) = ui_weak.upgrade() else {
+            return;
+        };
+        let snapshot = snapshot.borrow();
+        let Some(snapshot) = snapshot.as_ref() else {
+            ui.set_export_status("Nothing to export yet".into());
+            return;
+        };
+        let Some(path) = rfd::FileDialog::new().add_filter("PNG image", &["png"]).set_file_name("waveform.png").save_file() else {
+            return;
+        };
+        let width = ui.get_export_width().clamp(MIN_EXPORT_SIZE, MAX_EXPORT_SIZE) as u32;
+        let height = ui.get_export_height().clamp(MIN_EXPORT_SIZE, MAX_EXPORT_SIZE) as u32;
+        match export_image(&ui, snapshot, width, height, &path) {
+            Ok(()) => ui.set_export_status(format!("Saved {}", path.display()).into()),
+            Err(err) => {
+                eprintln!("Error saving {}: {}", path.display(), err);
+                ui.set_export_status(format!("Cannot save {}: {}", path.display(), err).into());
+            }
+        }
+    });
+
+    // Vom Timer abgeholt, der den Verlauf besitzt
+    let csv_export_path = Rc::new(RefCell::new(None::<PathBuf>));
+    let csv_export_path_for_ui = csv_export_path.clone();
+    ui.on_export_csv(move || {
+        if let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).set_file_name("waveform.csv").save_file() {
+            *csv_export_path_for_ui.borrow_mut() = Some(path);
+        }
+    });
+
+    let params_for_ui = params.clone();
+    ui.on_chunk_size_changed(move |size| {
+        params_for_ui.set_chunk_size(size.max(1) as usize);
+    });
+    let params_for_ui = params.clone();
+    ui.on_mode_changed(move |mode| {
+        params_for_ui.set_waveform_mode(WaveformMode::from_u8(mode as u8));
+    });
+    let params_for_ui = params.clone();
+    ui.on_downmix_changed(move |enabled| {
+        params_for_ui.set_downmix(enabled);
+    });
+    let params_for_ui = params.clone();
+    ui.on_dc_filter_changed(move |enabled, cutoff_hz| {
+        params_for_ui.set_dc_cutoff_hz(enabled.then_some(cutoff_hz.max(1) as u32));
+    });
+    let params_for_ui = params.clone();
+    ui.on_a_weighting_changed(move |enabled| {
+        params_for_ui.set_a_weighting(enabled);
+    });
+    let ui_weak = ui.as_weak();
+    let params_for_ui = params.clone();
+    ui.on_gate_changed(move || {
+        if let Some(ui) = ui_weak.upgrade() {
+            apply_gate(&ui, &params_for_ui);
+        }
+    });
+    let params_for_ui = params.clone();
+    ui.on_inverted_channels_changed(move |inverted| {
+        params_for_ui.set_inverted_channels(inverted as u32);
+    });
+    let ui_weak = ui.as_weak();
+    let params_for_ui = params.clone();
+    ui.on_envelope_changed(move || {
+        if let Some(ui) = ui_weak.upgrade() {
+            apply_envelope(&ui, &params_for_ui);
+        }
+    });
+    let ui_weak = ui.as_weak();
+    let params_for_ui = params.clone();
+    ui.on_envelope_preset_selected(move |index| {
+        if let Some(ui) = ui_weak.upgrade() {
+            let (attack, release) = EnvelopePreset::from_index(index).timing();
+            ui.set_envelope_attack_ms(attack.as_millis() as i32);
+            ui.set_envelope_release_ms(release.as_millis() as i32);
+            apply_envelope(&ui, &params_for_ui);
+        }
+    });
+    let ui_weak = ui.as_weak();
+    let connection_for_clips = connection.clone();
+    ui.on_clip_trigger_changed(move || {
+        if let (Some(ui), Some(connection)) = (ui_weak.upgrade(), connection_for_clips.borrow().as_ref()) {
+            arm_clips(&ui, connection.capture.recorder());
+        }
+    });
+    let history_len_for_ui = history_len.clone();
+    ui.on_history_len_changed(move |len| {
+        history_len_for_ui.set(len.max(1) as usize);
+    });
+
+    let ui_weak = ui.as_weak();
+    let connection_for_retry = connection.clone();
+    let paused_for_retry = paused.clone();
+    let connect_for_retry = connect.clone();
+    ui.on_retry(move || {
+        if let Some(ui) = ui_weak.upgrade() {
+            match open_connection(&ui, &connection_for_retry, &*connect_for_retry) {
+                // Ein neuer Stream läuft sofort los
+                Ok(()) => {
+                    paused_for_retry.set(false);
+                    ui.set_paused(false);
+                }
+                Err(err) => show_connection_error(&ui, &err),
+            }
+        }
+    });
+
+    let ui_weak = ui.as_weak();
+    let connection_for_switch = connection.clone();
+    let paused_for_switch = paused.clone();
+    let clear_for_switch = clear_requested.clone();
+    let loopback_for_switch = loopback.clone();
+    let connect_for_switch = connect.clone();
+    ui.on_switch_device(move |name| {
+        let Some(ui) = ui_weak.upgrade() else {
+            return;
+        };
+        let previous = (device.replace(Some(name.to_string())), loopback_for_switch.replace(false));
+        // Erst den alten Stream beenden, manche Hosts geben das Gerät sonst nicht frei
+        *connection_for_switch.borrow_mut() = None;
+        match open_connection(&ui, &connection_for_switch, &*connect_for_switch) {
+            Ok(()) => {
+                // Zeitbasis und Kanäle des Verlaufs gehören zum alten Gerät
+                clear_for_switch.set(true);
+                paused_for_switch.set(false);
+                ui.set_paused(false);
+                ui.set_loopback(false);
+                ui.set_device_status(format!("Switched to {}", name).into());
+            }
+            Err(err) => {
+                eprintln!("Error switching to {}: {}", name, err);
+                *device.borrow_mut() = previous.0;
+                loopback_for_switch.set(previous.1);
+                match open_connection(&ui, &connection_for_switch, &*connect_for_switch) {
+                    Ok(()) => ui.set_device_status(format!("Cannot open {}: {}. Back on the previous device", name, err).into()),
+                    Err(err) => show_connection_error(&ui, &err),
+                }
+            }
+        }
+    });
+
+    let ui_weak = ui.as_weak();
+    ui.on_loopback_changed(move |enabled| {
+        loopback.set(enabled);
+        if let Some(ui) = ui_weak.upgrade() {
+            ui.invoke_retry();
+        }
+    });
+
+    let ui_weak = ui.as_weak();
+    ui.on_buffer_ms_changed(move |ms| {
+        buffer_duration.set(Duration::from_millis(ms.max(1) as u64));
+        if let Some(ui) = ui_weak.upgrade() {
+            ui.invoke_retry();
+        }
+    });
+
+    let ui_weak = ui.as_weak();
+    let connection_for_record = connection.clone();
+    ui.on_record(move || {
+        let Some(ui) = ui_weak.upgrade() else {
+            return;
+        };
+        let connection = connection_for_record.borrow();
+        let Some(connection) = connection.as_ref() else {
+            return;
+        };
+        let path = recording_path("recording");
+        match connection.capture.recorder().start(&path) {
+            Ok(()) => {
+                ui.set_recording(true);
+                ui.set_recording_status(format!("Recording to {}", path.display()).into());
+            }
+            Err(err) => {
+                eprintln!("Error starting recording: {}", err);
+                ui.set_recording_status(format!("Cannot record to {}: {}", path.display(), err).into());
+            }
+        }
+    });
+
+    let ui_weak = ui.as_weak();
+    let connection_for_stop = connection.clone();
+    ui.on_stop(move || {
+        if let Some(connection) = connection_for_stop.borrow().as_ref() {
+            connection.capture.recorder().stop();
+        }
+        if let Some(ui) = ui_weak.upgrade() {
+            if ui.get_recording() {
+                ui.set_recording(false);
+                ui.set_recording_status("Recording saved".into());
+            }
+        }
+    });
+
+    let calibration_requested = Rc::new(Cell::new(false));
+    let calibration_requested_for_ui = calibration_requested.clone();
+    ui.on_calibrate(move || calibration_requested_for_ui.set(true));
+
+    let params_for_clear = params.clone();
+    let clear_requested_for_ui = clear_requested.clone();
+    let ui_weak = ui.as_weak();
+    ui.on_clear(move || {
+        // Der Audio-Thread verwirft Rest und Filterzustand selbst, die UI nur ihren Verlauf
+        params_for_clear.discard_remainder();
+        clear_requested_for_ui.set(true);
+        if let Some(ui) = ui_weak.upgrade() {
+            ui.invoke_reset_level_max();
+        }
+    });
+
+    let freeze_requested_for_ui = freeze_requested.clone();
+    ui.on_freeze(move || freeze_requested_for_ui.set(true));
 
-    // Starten des Audio-Streams
-    let waveform_data_clone = waveform_data.clone();
-    // Der Stream muss bis zum Programmende erhalten bleiben, daher außerhalb des Threads speichern
-    let stream = start_audio_stream(waveform_data_clone).expect("Failed to start audio stream");
-    // stream wird im Scope gehalten, damit es nicht gedroppt wird
+    let ui_weak = ui.as_weak();
+    let connection_for_pause = connection.clone();
+    let paused_for_pause = paused.clone();
+    let params_for_pause = params.clone();
+    ui.on_pause(move || {
+        let connection = connection_for_pause.borrow();
+        let Some(connection) = connection.as_ref() else {
+            return;
+        };
+        let now_paused = !paused_for_pause.get();
+        paused_for_pause.set(now_paused);
+        if now_paused {
+            if let Err(err) = connection.capture.pause() {
+                eprintln!("Error pausing stream: {}", err);
+            }
+        } else {
+            params_for_pause.discard_remainder();
+            if let Err(err) = connection.capture.resume() {
+                eprintln!("Error resuming stream: {}", err);
+            }
+        }
+        if let Some(ui) = ui_weak.upgrade() {
+            ui.set_paused(now_paused);
+        }
+    });
 
     // Timer für regelmäßiges Rendern (nutze Slint's Timer API, damit UI-Objekte nicht in Threads verschoben werden)
     let ui_weak = ui.as_weak();
-    let timer = slint::Timer::default();
-    timer.set_interval(std::time::Duration::from_millis(50));
-    let waveform_data_for_timer = waveform_data.clone();
-    timer.start(slint::TimerMode::Repeated, std::time::Duration::from_millis(50), move || {
+    // Der Timer passt sein Intervall selbst an, daher hält der Callback eine schwache Referenz
+    let timer = Rc::new(slint::Timer::default());
+    let timer_weak = Rc::downgrade(&timer);
+    // Der Verlauf gehört allein dem UI-Thread
+    let mut waveform_data = RingBuffer::<WaveformPoint>::new(history_len.get());
+    let mut was_paused = false;
+    let mut spectrogram = Spectrogram::new(DEFAULT_FFT_SIZE, MAX_PLOT_WIDTH as usize);
+    let mut scope = Scope::new(SAMPLE_QUEUE_CAPACITY);
+    let mut peak_hold = PeakHold::new(MAX_PLOT_WIDTH as usize);
+    let mut auto_scale = AutoScale::new();
+    let mut vu_meter = VuMeter::new();
+    let mut signal_detector = SignalDetector::new();
+    let mut clip_trigger = ClipTrigger::new();
+    let mut calibration: Option<Calibration> = None;
+    // Farbskala, mit der die Pegelbalken zuletzt gefüllt wurden
+    let mut vu_colormap = None;
+    let mut frozen_frames = VecDeque::<FrozenFrame>::new();
+    let mut last_frame = Instant::now();
+    let connection_for_timer = connection.clone();
+    let mut reconnect: Option<Reconnect> = None;
+    timer.start(slint::TimerMode::Repeated, DEFAULT_REFRESH_INTERVAL, move || {
+            if let (Some(ui), Some(timer)) = (ui_weak.upgrade(), timer_weak.upgrade()) {
+                let chunk_duration = chunk_duration(ui.get_chunk_size(), ui.get_channels(), ui.get_sample_rate());
+                let interval = refresh_interval(Duration::from_millis(ui.get_refresh_ms().max(0) as u64), chunk_duration);
+                if timer.interval() != interval {
+                    timer.set_interval(interval);
+                }
+                ui.set_effective_refresh_ms(interval.as_millis() as i32);
+            }
+
+            // Eine geöffnete Datei wird statt des Live-Eingangs angezeigt, ab der gewählten Position
+            if let (Some(ui), Some(file)) = (ui_weak.upgrade(), wav_file.borrow_mut().as_mut()) {
+                reconnect = None;
+                file.update(&params);
+                let width = plot_width(&ui);
+                let visible_len = requested_visible_len(&ui, width);
+                ui.set_visible_points(visible_len as i32);
+                let points = file.points();
+                ui.set_file_points(points.len() as i32);
+                let position = (ui.get_file_position().max(0) as usize).min(points.len().saturating_sub(visible_len));
+                let end = (position + visible_len).min(points.len());
+                let now = Instant::now();
+                let elapsed = now - last_frame;
+                last_frame = now;
+                if let Some(path) = csv_export_path.take() {
+                    export_csv(&ui, &path, points, 0, file.info);
+                }
+                let view = View { visible_len, width, pan: 0, first_index: position as u64 };
+                let snapshot = show_waveform(&ui, &points[position..end], view, file.info, &mut peak_hold, &mut auto_scale, elapsed);
+                *snapshot_for_timer.borrow_mut() = Some(snapshot);
+                return;
+            }
+
             if let Some(ui) = ui_weak.upgrade() {
-                let data = waveform_data_for_timer.lock().unwrap();
-                ui.set_wav1(slint::ModelRc::from(data.as_slice()));
-                ui.set_wav1start(((data.len() as isize) - 1000) as i32);
+                // Reißt der Stream ab (z.B. USB-Interface abgezogen), wird er verworfen und mit
+                // wachsendem Abstand neu geöffnet, ggf. auf dem neuen Standardgerät
+                let lost = connection_for_timer.borrow().as_ref().is_some_and(|c| c.capture.device_lost());
+                if lost {
+                    *connection_for_timer.borrow_mut() = None;
+                    reconnect = Some(Reconnect::new());
+                    ui.set_error_message("Device lost, reconnecting…".into());
+                    ui.set_recording(false);
+                }
+                if connection_for_timer.borrow().is_some() {
+                    // Z.B. über "Retry" wieder verbunden
+                    reconnect = None;
+                } else if let Some(state) = reconnect.as_mut() {
+                    if Instant::now() >= state.next_attempt {
+                        match open_connection(&ui, &connection_for_timer, &*connect) {
+                            Ok(()) => {
+                                reconnect = None;
+                                paused.set(false);
+                                ui.set_paused(false);
+                            }
+                            Err(err) => {
+                                eprintln!("Reconnect failed: {}", err);
+                                state.failed();
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Ohne Stream gibt es nichts anzuzeigen, die UI zeigt stattdessen den Fehler
+            let mut connection = connection_for_timer.borrow_mut();
+            let Some(Connection { capture, points: consumer, samples: sample_consumer }) = connection.as_mut() else {
+                signal_detector.reset();
+                clip_trigger.reset();
+                calibration = None;
+                if let Some(ui) = ui_weak.upgrade() {
+                    ui.set_signal_present(false);
+                    ui.set_calibration_countdown(0);
+                }
+                return;
+            };
+            let stream_info = capture.info();
+            if clear_requested.replace(false) {
+                waveform_data.clear();
+                consumer.clear();
+                if let Ok(chunk) = sample_consumer.read_chunk(sample_consumer.slots()) {
+                    chunk.commit_all();
+                }
+                peak_hold.reset();
+                vu_meter.reset();
+            }
+            if freeze_requested.replace(false) {
+                if frozen_frames.len() >= MAX_FROZEN_FRAMES {
+                    frozen_frames.pop_front();
+                }
+                frozen_frames.push_back(FrozenFrame { points: waveform_data.iter().copied().collect(), info: stream_info });
+                if let Some(ui) = ui_weak.upgrade() {
+                    ui.set_frozen_count(frozen_frames.len() as i32);
+                    ui.set_frozen_index(frozen_frames.len() as i32);
+                    ui.set_show_frozen(true);
+                }
+            }
+            if let (Some(path), Some(ui)) = (csv_export_path.take(), ui_weak.upgrade()) {
+                export_csv(&ui, &path, &waveform_data, waveform_data.first_index(), stream_info);
+            }
+            // Im Pausenmodus kommen keine neuen Punkte hinzu, der Verlauf kann aber verschoben werden
+            let is_paused = paused.get();
+            if is_paused {
+                was_paused = true;
+            } else if was_paused {
+                // Punkte, die noch während der Pause entstanden sind, verwerfen
+                consumer.clear();
+                if let Ok(chunk) = sample_consumer.read_chunk(sample_consumer.slots()) {
+                    chunk.commit_all();
+                }
+                peak_hold.reset();
+                was_paused = false;
+            }
+            // Zeit seit dem letzten Frame für den Abfall der Spitzenwerte, die Pause zählt nicht mit
+            let now = Instant::now();
+            let elapsed = if is_paused { Duration::ZERO } else { now - last_frame };
+            last_frame = now;
+            if let Some(ui) = ui_weak.upgrade() {
+                // Die Anzeige bleibt an, bis sie in der UI zurückgesetzt wird
+                if meters.take_clipped() {
+                    ui.set_clipped(true);
+                }
+                ui.set_xruns(meters.xruns() as i32);
+                ui.set_dropped_points(meters.dropped_points() as i32);
+                ui.set_dropped_record_frames(meters.dropped_record_frames() as i32);
+                let colormap = Colormap::from_index(ui.get_colormap());
+                if vu_colormap != Some(colormap) {
+                    ui.set_vu_fill(colormap.strip());
+                    vu_colormap = Some(colormap);
+                }
+                ui.set_gate_open(meters.gate_open());
+                if !is_paused {
+                    ui.set_correlation(meters.correlation());
+                    if ui.get_correlation_matrix() {
+                        ui.set_correlation_matrix_image(correlation_view::render(&meters.correlation_matrix(), ui.get_channels().max(1) as usize));
+                    }
+                    let (momentary, short_term, integrated) = meters.loudness();
+                    ui.set_loudness(format!("M {}  S {}  I {} LUFS", format_lufs(momentary), format_lufs(short_term), format_lufs(integrated)).into());
+                }
+                let width = plot_width(&ui);
+
+                params.set_mono_samples_enabled(ui.get_spectrogram() || ui.get_trigger());
+                if ui.get_spectrogram() {
+                    if is_paused {
+                        return;
+                    }
+                    spectrogram.set_fft_size(ui.get_fft_size().max(2) as usize);
+                    spectrogram.set_window(WindowKind::from_index(ui.get_fft_window()));
+                    if let Ok(chunk) = sample_consumer.read_chunk(sample_consumer.slots()) {
+                        let (first, second) = chunk.as_slices();
+                        spectrogram.push_samples(first);
+                        spectrogram.push_samples(second);
+                        chunk.commit_all();
+                    }
+                    // Eine Spalte je FFT-Frame, in der Höhe so viele Zeilen wie physische Pixel
+                    let (_, pixel_height) = physical_plot_size(width, ui.window().scale_factor());
+                    ui.set_plot_image(spectrogram.render(width as u32, pixel_height, Colormap::from_index(ui.get_colormap())));
+                    return;
+                }
+
+                // Oszilloskop: Samples ab dem letzten Trigger statt des reduzierten Verlaufs
+                if ui.get_trigger() {
+                    if is_paused {
+                        return;
+                    }
+                    if let Ok(chunk) = sample_consumer.read_chunk(sample_consumer.slots()) {
+                        let (first, second) = chunk.as_slices();
+                        scope.push_samples(first);
+                        scope.push_samples(second);
+                        chunk.commit_all();
+                    }
+                    let settings = trigger_settings(&ui);
+                    ui.set_triggered(scope.update(visible_points(width, zoom(&ui)), settings));
+                    let scale_factor = ui.window().scale_factor();
+                    let (pixel_width, pixel_height) = physical_plot_size(width, scale_factor);
+                    ui.set_plot_image(render_plot::render_scope(scope.frame(), pixel_width, pixel_height, &ui.get_plot_style(), Some(settings.level), ui.get_line_width().max(1) as u32, scale_factor));
+                    return;
+                }
+                scope.reset();
+
+                let visible_len = requested_visible_len(&ui, width);
+                ui.set_visible_points(visible_len as i32);
+                let mut received = 0;
+                if !is_paused {
+                    // Der Verlauf wächst, wenn die gewünschte Dauer mehr Punkte braucht
+                    received = receive_points(&mut consumer.lock(), &mut waveform_data, history_len.get().max(visible_len), &meters);
+                    if received > 0 {
+                        show_levels(&ui, waveform_data.iter().rev().take(received), stream_info);
+                    }
+                    let new_points = waveform_data.iter().skip(waveform_data.len().saturating_sub(received));
+                    show_signal_presence(&ui, &mut signal_detector, new_points.clone(), stream_info, elapsed);
+                    save_triggered_clip(&ui, capture.recorder(), &mut clip_trigger, new_points.clone(), stream_info, elapsed);
+                    if calibration_requested.replace(false) {
+                        calibration = Some(Calibration::new());
+                    }
+                    calibrate_gain(&ui, &mut calibration, new_points.clone(), stream_info, elapsed);
+                    show_vu_meter(&ui, &mut vu_meter, new_points, stream_info);
+                }
+                // Das Standbild wird wie der Verlauf in der Pause angezeigt, live geht es im Hintergrund weiter
+                let frozen = ui.get_show_frozen().then(|| frozen_frames.get((ui.get_frozen_index() - 1).max(0) as usize)).flatten();
+                if let Some(frame) = frozen {
+                    let pan = clamp_pan(ui.get_pan_offset(), frame.points.len(), visible_len);
+                    ui.set_pan_offset(pan as i32);
+                    let view = View { visible_len, width, pan, first_index: 0 };
+                    if ui.get_histogram() {
+                        show_histogram(&ui, &frame.points, frame.points.len(), view, frame.info);
+                        return;
+                    }
+                    let snapshot = show_waveform(&ui, &frame.points, view, frame.info, &mut peak_hold, &mut auto_scale, elapsed);
+                    *snapshot_for_timer.borrow_mut() = Some(snapshot);
+                    return;
+                }
+                // Live rastet die Ansicht am neuesten Punkt ein, in der Pause oder ohne "Follow" bleibt sie stehen
+                let pan = view_pan(ui.get_pan_offset(), received, ui.get_follow() && !is_paused, waveform_data.len(), visible_len);
+                ui.set_pan_offset(pan as i32);
+                let view = View { visible_len, width, pan, first_index: waveform_data.first_index() };
+                if ui.get_histogram() {
+                    show_histogram(&ui, &waveform_data, waveform_data.len(), view, stream_info);
+                    return;
+                }
+                let snapshot = show_waveform(&ui, &waveform_data, view, stream_info, &mut peak_hold, &mut auto_scale, elapsed);
+                *snapshot_for_timer.borrow_mut() = Some(snapshot);
             }
         });
 
-    ui.run()?;
-    drop(stream); // Stream wird hier gedroppt, wenn das UI geschlossen wird
+    // Geänderte Einstellungen werden gesammelt gespeichert statt bei jeder Bewegung eines Sliders
+    let settings_timer = primary.then(|| {
+        let settings_timer = slint::Timer::default();
+        let ui_weak = ui.as_weak();
+        let connection_for_settings = connection.clone();
+        let mut saved = settings.clone();
+        settings_timer.start(slint::TimerMode::Repeated, SETTINGS_SAVE_INTERVAL, move || {
+            let Some(ui) = ui_weak.upgrade() else {
+                return;
+            };
+            // Ohne Stream bleibt das zuletzt geöffnete Gerät gespeichert
+            let device = connection_for_settings.borrow().as_ref().map_or_else(|| saved.device.clone(), |connection| Some(connection.capture.device().to_string()));
+            let current = current_settings(&ui, device);
+            if current != saved {
+                current.save();
+                saved = current;
+            }
+        });
+        settings_timer
+    });
+
+    ui.show()?;
+    app.windows.borrow_mut().push(CaptureWindow { id, _ui: ui, _timer: timer, _settings_timer: settings_timer });
     Ok(())
 }
 
-fn start_audio_stream(waveform_data: Arc<Mutex<SharedVector<(f32, f32)>>>) -> Result<Stream, Box<dyn Error>> {
-    let host = cpal::default_host();
-    let device = host.default_input_device().expect("No input device available");
-    println!("Using input device: {}", device.name()?);
-
-    let config = device.default_input_config().expect("Error retrieving default configuration");
-    println!("StreamConfig: {:?}", config);
-    let sample_format = config.sample_format();
-    println!("Sample format: {:?}", sample_format);
-
-    let supported_config = cpal::StreamConfig {
-        channels: config.channels(),
-        sample_rate: SampleRate(48000),
-        buffer_size: match config.buffer_size() {
-            cpal::SupportedBufferSize::Range { min, max } => {
-                println!("Buffer Size Range: min = {}, max = {}", min, max);
-                let size = (*max).min(1024 * 4 * 1024);
-                if size < *min {
-                    println!("Buffer size adjusted to minimum: {}", min);
-                    cpal::BufferSize::Fixed(*min)
-                } else if size >= *max {
-                    println!("Buffer Size: Unknown");
-                    cpal::BufferSize::Default
-                } else {
-                    println!("Buffer Size: {}", size);
-                    cpal::BufferSize::Fixed(size)
-                }
-            }
-            cpal::SupportedBufferSize::Unknown => {
-                println!("Buffer Size: Unknown");
-                cpal::BufferSize::Default
+/// Laufender Stream mit den Queues, aus denen die UI liest.
+struct Connection {
+    /// Beendet beim Drop den Stream und eine laufende Aufnahme
+    capture: WaveformCapture,
+    points: PointReceiver,
+    samples: Consumer<f32>,
+}
+
+/// Startet den Stream über `connect` und übernimmt ihn bei Erfolg in die UI, ein eventuell
+/// vorhandener Stream wird dabei ersetzt.
+fn open_connection(ui: &AppWindow, connection: &RefCell<Option<Connection>>, connect: &dyn Fn() -> Result<Connection, AudioError>) -> Result<(), AudioError> {
+    let new_connection = connect()?;
+    show_stream_info(ui, &new_connection.capture.info());
+    ui.set_device_name(new_connection.capture.device().into());
+    ui.set_error_message("".into());
+    // Eine Aufnahme gehört zum alten Stream und endet mit ihm
+    ui.set_recording(false);
+    arm_clips(ui, new_connection.capture.recorder());
+    *connection.borrow_mut() = Some(new_connection);
+    Ok(())
+}
+
+/// Dateiname für eine neue Aufnahme im aktuellen Verzeichnis, eindeutig über die Startzeit.
+fn recording_path(prefix: &str) -> PathBuf {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    PathBuf::from(format!("{}-{}.wav", prefix, seconds))
+}
+
+/// Schaltet ausgelöste Clips laut UI für den Recorder des Streams scharf oder ab.
+fn arm_clips(ui: &AppWindow, recorder: &Recorder) {
+    recorder.arm(ui.get_clip_trigger().then(|| Duration::from_secs(ui.get_clip_pre_roll_s().max(0) as u64)));
+}
+
+/// Speichert einen Clip mit Vorlauf, wenn der Pegel in den neuen Punkten eines Kanals die
+/// Schwelle erreicht. Während einer Aufnahme über "Record" wird nichts ausgelöst.
+fn save_triggered_clip<'a>(ui: &AppWindow, recorder: &Recorder, trigger: &mut ClipTrigger, points: impl IntoIterator<Item = &'a WaveformPoint>, stream_info: StreamInfo, elapsed: Duration) {
+    if !ui.get_clip_trigger() || ui.get_recording() {
+        trigger.reset();
+        return;
+    }
+    let channels = stream_info.channels.clamp(1, MAX_CHANNELS);
+    let level = points.into_iter().fold(0.0f32, |level, point| point.levels()[..channels].iter().fold(level, |level, &l| level.max(l)));
+    if !trigger.update(level, ui.get_clip_threshold_db() as f32, elapsed, recorder.clip_running()) {
+        return;
+    }
+    let path = recording_path("clip");
+    match recorder.save_clip(&path, Duration::from_secs(ui.get_clip_length_s().max(1) as u64)) {
+        Ok(()) => ui.set_recording_status(format!("Saving clip to {}", path.display()).into()),
+        Err(err) => {
+            eprintln!("Error saving clip: {}", err);
+            ui.set_recording_status(format!("Cannot save clip to {}: {}", path.display(), err).into());
+        }
+    }
+}
+
+/// Übernimmt gespeicherte Einstellungen in die UI. Chunk-Größe und Verlaufslänge setzt `open_window`
+/// zusammen mit den Werten für den Audio-Thread.
+fn apply_settings(ui: &AppWindow, settings: &Settings) {
+    ui.set_loopback(settings.loopback);
+    ui.set_gain_db(settings.gain_db);
+    ui.set_scale_mode(settings.scale_mode.clamp(0, 3));
+    ui.set_auto_attack_ms(settings.auto_attack_ms);
+    ui.set_auto_release_ms(settings.auto_release_ms);
+    ui.set_plot_style_index(settings.plot_style.clamp(0, slint::Model::row_count(&ui.get_plot_styles()) as i32 - 1));
+    ui.set_image_plot(settings.image_plot);
+    ui.set_db_scale(settings.db_scale);
+    ui.set_db_floor(settings.db_floor);
+    ui.set_grid(settings.grid);
+    ui.set_gradient(settings.gradient);
+    ui.set_time_axis(settings.time_axis);
+    ui.set_peak_hold(settings.peak_hold);
+    ui.set_column_range(settings.column_range);
+    ui.set_line_width(settings.line_width.clamp(1, 8));
+    ui.set_plot_layout(settings.plot_layout);
+    ui.set_colormap(settings.colormap.clamp(0, 3));
+    ui.set_smoothing_mode(settings.smoothing_mode.clamp(0, 2));
+    ui.set_smoothing_window(settings.smoothing_window.max(1));
+    ui.set_peak_decay_db(settings.peak_decay_db);
+    ui.set_dc_filter(settings.dc_filter);
+    ui.set_dc_cutoff_hz(settings.dc_cutoff_hz);
+    ui.set_gate(settings.gate);
+    ui.set_gate_threshold_db(settings.gate_threshold_db);
+    ui.set_gate_hold_ms(settings.gate_hold_ms);
+    ui.set_gate_release_ms(settings.gate_release_ms);
+    ui.set_envelope(settings.envelope);
+    ui.set_envelope_attack_ms(settings.envelope_attack_ms);
+    ui.set_envelope_release_ms(settings.envelope_release_ms);
+    ui.set_clip_trigger(settings.clip_trigger);
+    ui.set_clip_threshold_db(settings.clip_threshold_db);
+    ui.set_clip_pre_roll_s(settings.clip_pre_roll_s);
+    ui.set_clip_length_s(settings.clip_length_s);
+    ui.set_refresh_ms(settings.refresh_ms);
+}
+
+/// Aktuelle Einstellungen der UI zum Speichern.
+fn current_settings(ui: &AppWindow, device: Option<String>) -> Settings {
+    Settings {
+        device,
+        loopback: ui.get_loopback(),
+        buffer_ms: ui.get_buffer_ms().max(1) as u32,
+        chunk_size: ui.get_chunk_size().max(1) as usize,
+        history_len: ui.get_history_len().max(1) as usize,
+        gain_db: ui.get_gain_db(),
+        scale_mode: ui.get_scale_mode(),
+        auto_attack_ms: ui.get_auto_attack_ms(),
+        auto_release_ms: ui.get_auto_release_ms(),
+        plot_style: ui.get_plot_style_index(),
+        image_plot: ui.get_image_plot(),
+        db_scale: ui.get_db_scale(),
+        db_floor: ui.get_db_floor(),
+        grid: ui.get_grid(),
+        gradient: ui.get_gradient(),
+        time_axis: ui.get_time_axis(),
+        peak_hold: ui.get_peak_hold(),
+        column_range: ui.get_column_range(),
+        line_width: ui.get_line_width(),
+        plot_layout: ui.get_plot_layout(),
+        colormap: ui.get_colormap(),
+        smoothing_mode: ui.get_smoothing_mode(),
+        smoothing_window: ui.get_smoothing_window(),
+        peak_decay_db: ui.get_peak_decay_db(),
+        dc_filter: ui.get_dc_filter(),
+        dc_cutoff_hz: ui.get_dc_cutoff_hz(),
+        gate: ui.get_gate(),
+        gate_threshold_db: ui.get_gate_threshold_db(),
+        gate_hold_ms: ui.get_gate_hold_ms(),
+        gate_release_ms: ui.get_gate_release_ms(),
+        envelope: ui.get_envelope(),
+        envelope_attack_ms: ui.get_envelope_attack_ms(),
+        envelope_release_ms: ui.get_envelope_release_ms(),
+        clip_trigger: ui.get_clip_trigger(),
+        clip_threshold_db: ui.get_clip_threshold_db(),
+        clip_pre_roll_s: ui.get_clip_pre_roll_s(),
+        clip_length_s: ui.get_clip_length_s(),
+        refresh_ms: ui.get_refresh_ms(),
+    }
+}
+
+/// Übernimmt die Einstellungen des Noise-Gates aus der UI für den Audio-Thread.
+fn apply_gate(ui: &AppWindow, params: &Params) {
+    params.set_gate_threshold_db(ui.get_gate().then_some(ui.get_gate_threshold_db() as f32));
+    params.set_gate_timing_ms(ui.get_gate_hold_ms().max(0) as u32, ui.get_gate_release_ms().max(0) as u32);
+}
+
+/// Übernimmt die Einstellungen des Hüllkurvenfolgers aus der UI für den Audio-Thread.
+fn apply_envelope(ui: &AppWindow, params: &Params) {
+    params.set_envelope(ui.get_envelope());
+    params.set_envelope_timing_ms(ui.get_envelope_attack_ms().max(0) as u32, ui.get_envelope_release_ms().max(0) as u32);
+}
+
+/// Zeigt die Eigenschaften des Streams bzw. der Datei in der Statuszeile an.
+fn show_stream_info(ui: &AppWindow, info: &StreamInfo) {
+    ui.set_sample_rate(info.sample_rate as i32);
+    ui.set_device_sample_rate(info.device_sample_rate as i32);
+    ui.set_requested_sample_rate(info.requested_sample_rate.map_or(0, |rate| rate as i32));
+    ui.set_sample_format(info.sample_format.map_or_else(String::new, |format| format!("{:?}", format)).into());
+    ui.set_channels(info.channels as i32);
+    ui.set_buffer_size(info.buffer_size.map_or(0, |size| size as i32));
+    ui.set_requested_buffer_size(info.requested_buffer_size.map_or(0, |size| size as i32));
+    ui.set_latency_ms(info.latency().map_or(0.0, |latency| latency.as_secs_f32() * 1000.0));
+}
+
+/// Zeigt einen fehlgeschlagenen Start des Streams im Fehlerbanner an.
+fn show_connection_error(ui: &AppWindow, err: &AudioError) {
+    eprintln!("Failed to start audio stream: {}", err);
+    ui.set_error_message(format!("No audio input: {}. {}", err, err.hint()).into());
+}
+
+/// Zustand der automatischen Wiederverbindung nach Verlust des Geräts.
+struct Reconnect {
+    delay: Duration,
+    next_attempt: Instant,
+}
+
+impl Reconnect {
+    fn new() -> Self {
+        Reconnect { delay: RECONNECT_INITIAL_DELAY, next_attempt: Instant::now() + RECONNECT_INITIAL_DELAY }
+    }
+
+    /// Verdoppelt nach einem Fehlversuch die Wartezeit bis höchstens `RECONNECT_MAX_DELAY`.
+    fn failed(&mut self) {
+        self.delay = (self.delay * 2).min(RECONNECT_MAX_DELAY);
+        self.next_attempt = Instant::now() + self.delay;
+    }
+}
+
+/// Sichtbarer Ausschnitt des Verlaufs.
+#[derive(Clone, Copy, Debug)]
+struct View {
+    /// Anzahl sichtbarer Punkte
+    visible_len: usize,
+    /// Breite des Plots in Pixeln
+    width: usize,
+    /// Anzahl Punkte zwischen dem neuesten Punkt und dem rechten Rand
+    pan: usize,
+    /// Fortlaufende Nummer des ersten Punktes, an ihr richten sich die Spalten aus (siehe
+    /// `render_plot::aligned_start`)
+    first_index: u64,
+}
+
+/// Zeigt die Verteilung der Pegel im sichtbaren Ausschnitt der `len` Punkte als Histogramm. Es
+/// zählen die angezeigten Kanäle ohne Verstärkung, ein Kanal auf beiden Spuren nur einmal.
+fn show_histogram<'a>(ui: &AppWindow, points: impl IntoIterator<Item = &'a WaveformPoint>, len: usize, view: View, stream_info: StreamInfo) {
+    let (a, b) = trace_channels(ui.get_trace_a_channel(), ui.get_trace_b_channel(), stream_info.channels);
+    let [a_hidden, b_hidden] = hidden_lanes(ui, stream_info);
+    let start = view_start(len, view.visible_len) as i64 - view.pan as i64;
+    let end = (start + view.visible_len as i64).max(0) as usize;
+    let start = start.max(0) as usize;
+    let visible = points.into_iter().skip(start).take(end.saturating_sub(start));
+    let levels = visible.flat_map(|point| [(!a_hidden).then(|| point.level(a)), (!b_hidden && b != a).then(|| point.level(b))].into_iter().flatten());
+    let scale = if ui.get_histogram_db() { AmplitudeScale::Db { floor_db: ui.get_db_floor() } } else { AmplitudeScale::Linear };
+    let counts = histogram::count(levels, histogram::BINS, scale);
+    let scale_factor = ui.window().scale_factor();
+    let (pixel_width, pixel_height) = physical_plot_size(view.width, scale_factor);
+    ui.set_plot_image(render_plot::render_histogram(&counts, pixel_width, pixel_height, &ui.get_plot_style(), scale_factor));
+}
+
+/// Zeigt `view.visible_len` Punkte als Verlauf über die `view.width` Spalten des Plots an, der
+/// neueste Punkt um `view.pan` Punkte rechts außerhalb. Sind es weniger Punkte, bleibt der linke
+/// Teil leer. Die Darstellung (nativ oder gerastert, Skala, Verstärkung) kommt aus der UI.
+/// Liefert den angezeigten Ausschnitt nach der Verstärkung, z.B. für den Bildexport.
+fn show_waveform<'a>(ui: &AppWindow, points: impl IntoIterator<Item = &'a WaveformPoint>, view: View, stream_info: StreamInfo, peak_hold: &mut PeakHold, auto_scale: &mut AutoScale, elapsed: Duration) -> PlotSnapshot {
+    let (a, b) = trace_channels(ui.get_trace_a_channel(), ui.get_trace_b_channel(), stream_info.channels);
+    let [a_hidden, b_hidden] = hidden_lanes(ui, stream_info);
+    ui.set_trace_a_hidden(a_hidden);
+    ui.set_trace_b_hidden(b_hidden);
+    // Betrag für Pegel, Skalierung und Analysen, die Hüllkurve mit Vorzeichen für den gerasterten Plot
+    let mut envelope = Envelope::default();
+    let mut times = Vec::new();
+    let mut gaps = Vec::new();
+    let mut traces: Vec<TracePoint> = points
+        .into_iter()
+        .enumerate()
+        .map(|(idx, point)| {
+            times.push(point.time.as_secs_f64());
+            if point.gap {
+                gaps.push(idx);
             }
-        },
-    };
+            envelope.lows.push((point.min[a], point.min[b]));
+            envelope.highs.push((point.max[a], point.max[b]));
+            (point.level(a), point.level(b))
+        })
+        .collect();
+    let vertical = vertical_scale(ui);
+    let mut scale = display_scale(&traces, vertical);
+    match vertical {
+        VerticalScale::SmoothAuto { attack, release } => scale = auto_scale.update(scale, elapsed, attack, release),
+        _ => auto_scale.reset(),
+    }
+    for (left, right) in traces.iter_mut().chain(envelope.lows.iter_mut()).chain(envelope.highs.iter_mut()) {
+        *left *= scale;
+        *right *= scale;
+    }
+    let View { visible_len, width, pan, first_index } = view;
+    let start = view_start(traces.len(), visible_len) - pan as i32;
+    let smoothing = envelope_smoothing(ui);
+    let smoothed = smoothing::smooth(&traces, smoothing);
+    let shown = smoothed.as_deref().unwrap_or(&traces);
+    let smoothed_envelope = smoothing::smooth_envelope(&envelope, smoothing);
+    let shown_envelope = smoothed_envelope.as_ref().unwrap_or(&envelope);
+    if ui.get_image_plot() {
+        // Das Bild wird in physischen Pixeln gerendert und von Slint in logischer Größe gezeigt,
+        // damit es auf HiDPI-Bildschirmen scharf bleibt
+        let scale_factor = ui.window().scale_factor();
+        let (pixel_width, pixel_height) = physical_plot_size(width, scale_factor);
+        // Je nach Zoom werden Punkte gestreckt oder zusammengefasst, danach gehört zu jeder Spalte ein Wert
+        let aligned = render_plot::aligned_start(start, first_index, visible_len, pixel_width);
+        let columns = render_plot::fit_to_width(shown, aligned, visible_len, pixel_width);
+        if ui.get_peak_hold() {
+            // Spitzenwerte und Onsets bleiben bei der Glättung genau
+            let raw_columns = smoothed.is_some().then(|| render_plot::fit_to_width(&traces, aligned, visible_len, pixel_width));
+            peak_hold.update(raw_columns.as_deref().unwrap_or(&columns), 0, elapsed, ui.get_peak_decay_db());
+        } else {
+            peak_hold.reset();
+        }
+        let peaks = ui.get_peak_hold().then_some(peak_hold.peaks());
+        let onsets = onset_columns(ui, &traces, aligned, visible_len, pixel_width, stream_info);
+        let column_min = column_minimum(ui, shown, aligned, visible_len, pixel_width);
+        let column_envelope = render_plot::fit_envelope(shown_envelope, aligned, visible_len, pixel_width);
+        let ages = column_ages(&times, aligned, visible_len, pixel_width);
+        let gap_columns = gap_columns(ui, &gaps, aligned, visible_len, pixel_width);
+        let mut options = PlotOptions {
+            column_min: column_min.as_deref(),
+            envelope: Some(&column_envelope),
+            gaps: &gap_columns,
+            ..plot_options(ui, stream_info, render_plot::points_per_column(visible_len, pixel_width) as f32, pan, peaks, &onsets, scale_factor)
+        };
+        if let Some(axis) = options.time_axis.as_mut() {
+            axis.column_ages = Some(&ages);
+        }
+        ui.set_plot_image(render_plot::render_plot(&columns, 0, pixel_width, pixel_height, &ui.get_plot_style(), &options));
+    } else {
+        let aligned = render_plot::aligned_start(start, first_index, visible_len, width as u32);
+        let columns = render_plot::fit_to_width(shown, aligned, visible_len, width as u32);
+        // Der native Plot ist linear, daher die Werte vorab in dieselbe Skala umrechnen
+        let amplitude_scale = amplitude_scale(ui);
+        let lanes: Vec<TracePoint> = if amplitude_scale != AmplitudeScale::Linear {
+            columns
+                .iter()
+                .map(|&(left, right)| (amplitude_scale.lane_fraction(left) * LANE_FULL_SCALE, amplitude_scale.lane_fraction(right) * LANE_FULL_SCALE))
+                .collect()
+        } else {
+            columns
+        };
+        ui.set_wav1(slint::ModelRc::from(lanes.as_slice()));
+    }
+    // Die Spalten beginnen bereits am linken Rand
+    ui.set_wav1start(0);
+    PlotSnapshot { traces: smoothed.unwrap_or(traces), envelope: smoothed_envelope.unwrap_or(envelope), times, gaps, start, visible_len, pan, info: stream_info }
+}
+
+/// Zoom laut UI in Spalten je Punkt, jede Stufe verdoppelt.
+fn zoom(ui: &AppWindow) -> f32 {
+    2f32.powi(ui.get_zoom_level())
+}
+
+/// Aktuelle Breite des Plots in Pixeln laut UI, begrenzt auf `1..=MAX_PLOT_WIDTH`.
+fn plot_width(ui: &AppWindow) -> usize {
+    ui.get_plot_columns().clamp(1, MAX_PLOT_WIDTH as i32) as usize
+}
+
+/// Größe des gerasterten Plots in physischen Pixeln für `width` logische Spalten, die Breite
+/// begrenzt auf `MAX_PLOT_WIDTH`.
+fn physical_plot_size(width: usize, scale_factor: f32) -> (u32, u32) {
+    let scale_factor = if scale_factor.is_finite() && scale_factor > 0.0 { scale_factor } else { 1.0 };
+    let pixel_width = ((width as f32 * scale_factor).round() as u32).clamp(1, MAX_PLOT_WIDTH);
+    let pixel_height = ((PLOT_HEIGHT as f32 * scale_factor).round() as u32).max(1);
+    (pixel_width, pixel_height)
+}
+
+/// Index des Punktes am linken Rand, damit der neueste von `len` Punkten bei `visible_len`
+/// sichtbaren Punkten am rechten Rand liegt. Negativ, wenn die Punkte den Plot nicht füllen.
+fn view_start(len: usize, visible_len: usize) -> i32 {
+    (len as i64 - visible_len as i64) as i32
+}
+
+/// Begrenzt die Verschiebung in die Vergangenheit auf den vorhandenen Verlauf.
+fn clamp_pan(pan: i32, len: usize, visible_len: usize) -> usize {
+    (pan.max(0) as usize).min(len.saturating_sub(visible_len))
+}
+
+/// Verschiebung des Ausschnitts für das nächste Bild. Bei `follow` liegt der neueste Punkt am
+/// rechten Rand. Sonst bleibt der Ausschnitt auf denselben Punkten stehen: `received` neue Punkte
+/// schieben ihn vom neuesten Punkt weg, und fallen alte Punkte aus dem Verlauf, bleibt er am
+/// ältesten vorhandenen Punkt stehen.
+fn view_pan(pan: i32, received: usize, follow: bool, len: usize, visible_len: usize) -> usize {
+    if follow {
+        return 0;
+    }
+    clamp_pan(pan.saturating_add(received.min(i32::MAX as usize) as i32), len, visible_len)
+}
+
+/// Anzahl sichtbarer Punkte bei `width` Spalten und `zoom` Spalten je Punkt.
+fn visible_points(width: usize, zoom: f32) -> usize {
+    ((width as f32 / zoom).round() as usize).max(1)
+}
+
+/// Anzahl Punkte für `seconds` Sekunden bei der Dauer `chunk_duration` eines Punktes,
+/// `None` ohne Stream.
+fn points_for_duration(seconds: f32, chunk_duration: Duration) -> Option<usize> {
+    if chunk_duration.is_zero() {
+        return None;
+    }
+    Some(((seconds.max(0.0) as f64 / chunk_duration.as_secs_f64()).ceil() as usize).max(1))
+}
+
+/// Anzahl sichtbarer Punkte laut UI: die gewählte Dauer in Sekunden oder der Zoom. Folgt
+/// Abtastrate und Chunk-Größe, da beide in jedem Frame neu gelesen werden.
+fn requested_visible_len(ui: &AppWindow, width: usize) -> usize {
+    let duration = chunk_duration(ui.get_chunk_size(), ui.get_channels(), ui.get_sample_rate());
+    match ui.get_duration_mode().then(|| points_for_duration(ui.get_history_seconds(), duration)).flatten() {
+        Some(points) => points,
+        None => visible_points(width, zoom(ui)),
+    }
+}
+
+/// Trigger-Einstellungen gemäß der UI.
+fn trigger_settings(ui: &AppWindow) -> TriggerSettings {
+    TriggerSettings {
+        level: ui.get_trigger_level().clamp(-1.0, 1.0),
+        edge: if ui.get_trigger_falling() { Edge::Falling } else { Edge::Rising },
+        mode: if ui.get_trigger_free_run() { TriggerMode::Auto } else { TriggerMode::Normal },
+    }
+}
+
+/// Vertikale Skala gemäß der UI.
+fn amplitude_scale(ui: &AppWindow) -> AmplitudeScale {
+    if ui.get_db_scale() {
+        AmplitudeScale::Db { floor_db: ui.get_db_floor() }
+    } else {
+        AmplitudeScale::Linear
+    }
+}
+
+/// Optionen für den gerasterten Plot gemäß der UI. `points_per_column` gibt an, wie viele
+/// Punkte auf eine Pixelspalte fallen, `pan` wie weit der neueste Punkt rechts außerhalb liegt.
+fn plot_options<'a>(ui: &AppWindow, stream_info: StreamInfo, points_per_column: f32, pan: usize, peak_hold: Option<&'a [TracePoint]>, onsets: &'a [u32], scale_factor: f32) -> PlotOptions<'a> {
+    PlotOptions {
+        scale: amplitude_scale(ui),
+        layout: if ui.get_plot_layout() == 1 { PlotLayout::Overlay } else { PlotLayout::Stacked },
+        fill: if ui.get_gradient() { TraceFill::Gradient } else { TraceFill::Solid },
+        grid_levels_db: ui.get_grid().then_some(GRID_LEVELS_DB.as_slice()),
+        time_axis: ui.get_time_axis().then_some(TimeAxis {
+            sample_rate: stream_info.sample_rate,
+            chunk_size: ui.get_chunk_size().max(1) as usize,
+            channels: stream_info.channels,
+            points_per_column,
+            pan_points: pan,
+            column_ages: None,
+        }),
+        peak_hold,
+        column_min: None,
+        envelope: None,
+        onsets,
+        gaps: &[],
+        hidden: hidden_lanes(ui, stream_info),
+        line_width: ui.get_line_width().max(1) as u32,
+        scale_factor,
+    }
+}
+
+/// Kleinster Wert je Spalte, wenn die Streuung angezeigt werden soll und mehrere Punkte in eine
+/// Spalte fallen, sonst `None`.
+fn column_minimum(ui: &AppWindow, traces: &[TracePoint], start: f64, visible_len: usize, width: u32) -> Option<Vec<TracePoint>> {
+    (ui.get_column_range() && render_plot::points_per_column(visible_len, width) > 1.0).then(|| render_plot::column_minimum(traces, start, visible_len, width))
+}
+
+/// Spalten mit erkanntem Einsatz im Ausschnitt ab `start`, leer solange die Marker aus sind.
+/// Erkannt wird über alle `traces` statt nur den Ausschnitt, damit die Marker beim Scrollen
+/// nicht springen.
+fn onset_columns(ui: &AppWindow, traces: &[TracePoint], start: f64, visible_len: usize, width: u32, stream_info: StreamInfo) -> Vec<u32> {
+    if !ui.get_onsets() {
+        return Vec::new();
+    }
+    let chunk_duration = chunk_duration(ui.get_chunk_size(), stream_info.channels as i32, stream_info.sample_rate as i32);
+    let min_interval = points_for_duration(ui.get_onset_interval_ms() as f32 / 1000.0, chunk_duration).unwrap_or(1);
+    onset::detect_onsets(traces.iter().map(|&(left, right)| left.max(right)), ui.get_onset_threshold_db(), min_interval)
+        .into_iter()
+        .filter_map(|idx| render_plot::point_column(idx, start, visible_len, width))
+        .collect()
+}
+
+/// Spalten der Punkte `gaps` im Ausschnitt ab `start`, leer solange die Markierung aus ist.
+fn gap_columns(ui: &AppWindow, gaps: &[usize], start: f64, visible_len: usize, width: u32) -> Vec<u32> {
+    if !ui.get_gap_markers() {
+        return Vec::new();
+    }
+    gaps.iter().filter_map(|&idx| render_plot::point_column(idx, start, visible_len, width)).collect()
+}
+
+/// Alter jeder Spalte in Sekunden vor dem neuesten der Punkte mit den Zeiten `times`, für die
+/// Zeitachse (siehe `TimeAxis::column_ages`).
+fn column_ages(times: &[f64], start: f64, visible_len: usize, width: u32) -> Vec<f32> {
+    let newest = times.last().copied().unwrap_or(0.0);
+    render_plot::column_times(times, start, visible_len, width).into_iter().map(|time| (newest - time) as f32).collect()
+}
+
+/// Schreibt die `points` als CSV nach `path` und meldet das Ergebnis in der UI. Die Zeit ergibt
+/// sich aus der Chunk-Größe der UI und der Abtastrate in `info`.
+fn export_csv<'a>(ui: &AppWindow, path: &Path, points: impl IntoIterator<Item = &'a WaveformPoint>, first_index: u64, info: StreamInfo) {
+    let duration = chunk_duration(ui.get_chunk_size(), info.channels as i32, info.sample_rate as i32);
+    match csv_export::save(path, points, first_index, info.channels, duration) {
+        Ok(()) => ui.set_export_status(format!("Saved {}", path.display()).into()),
+        Err(err) => {
+            eprintln!("Error saving {}: {}", path.display(), err);
+            ui.set_export_status(format!("Cannot save {}: {}", path.display(), err).into());
+        }
+    }
+}
+
+/// Standbild des ganzen Verlaufs, unabhängig vom weiterlaufenden Live-Verlauf.
+struct FrozenFrame {
+    points: Vec<WaveformPoint>,
+    info: StreamInfo,
+}
 
-    let stream = match sample_format {
-        cpal::SampleFormat::I16 => {
-            println!("Using I16 sample format");
-            device.build_input_stream(
-                &supported_config,
-                move |data: &[i16], _| process_audio(data, &waveform_data),
-                err_fn,
-                None,
-            )?
-        }
-        cpal::SampleFormat::F32 => {
-            println!("Using F32 sample format");
-            device.build_input_stream(
-                &supported_config,
-                move |data: &[f32], _| process_audio(data, &waveform_data),
-                err_fn,
-                None,
-            )?
-        }
-        _ => return Err("Unsupported sample format".into()),
+/// Zuletzt angezeigter Ausschnitt, Grundlage für den Bildexport.
+struct PlotSnapshot {
+    traces: Vec<TracePoint>,
+    envelope: Envelope,
+    /// Zeit jedes Punktes in Sekunden
+    times: Vec<f64>,
+    /// Indizes der Punkte, vor denen Samples fehlen
+    gaps: Vec<usize>,
+    /// Index des Punktes am linken Rand
+    start: i32,
+    /// Anzahl sichtbarer Punkte
+    visible_len: usize,
+    /// Anzahl Punkte zwischen dem neuesten Punkt und dem rechten Rand
+    pan: usize,
+    info: StreamInfo,
+}
+
+/// Rendert den Ausschnitt in der Größe `width` x `height` und speichert ihn als PNG.
+fn export_image(ui: &AppWindow, snapshot: &PlotSnapshot, width: u32, height: u32, path: &Path) -> image::ImageResult<()> {
+    let start = snapshot.start as f64;
+    let columns = render_plot::fit_to_width(&snapshot.traces, start, snapshot.visible_len, width);
+    let onsets = onset_columns(ui, &snapshot.traces, start, snapshot.visible_len, width, snapshot.info);
+    let column_min = column_minimum(ui, &snapshot.traces, start, snapshot.visible_len, width);
+    let column_envelope = render_plot::fit_envelope(&snapshot.envelope, start, snapshot.visible_len, width);
+    let ages = column_ages(&snapshot.times, start, snapshot.visible_len, width);
+    let gap_columns = gap_columns(ui, &snapshot.gaps, start, snapshot.visible_len, width);
+    let mut options = PlotOptions {
+        column_min: column_min.as_deref(),
+        envelope: Some(&column_envelope),
+        gaps: &gap_columns,
+        ..plot_options(ui, snapshot.info, render_plot::points_per_column(snapshot.visible_len, width) as f32, snapshot.pan, None, &onsets, 1.0)
     };
+    if let Some(axis) = options.time_axis.as_mut() {
+        axis.column_ages = Some(&ages);
+    }
+    let img = render_plot::render_plot_buffer(&columns, 0, width, height, &ui.get_plot_style(), &options);
+    render_plot::save_png(img, path)
+}
+
+/// Dauer eines Chunks: Samples je Chunk / Kanäle / Abtastrate, ohne Stream 0.
+fn chunk_duration(chunk_size: i32, channels: i32, sample_rate: i32) -> Duration {
+    if chunk_size <= 0 || channels <= 0 || sample_rate <= 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_secs_f64(chunk_size as f64 / channels as f64 / sample_rate as f64)
+}
 
-    stream.play()?;
-    println!("Audio stream started and playing.");
-    Ok(stream)
+/// Intervall des Render-Timers: die Einstellung, aber nicht kürzer als ein Chunk, da vorher keine
+/// neuen Punkte vorliegen, und immer in `MIN_REFRESH_INTERVAL..=MAX_REFRESH_INTERVAL`.
+fn refresh_interval(requested: Duration, chunk_duration: Duration) -> Duration {
+    requested.max(chunk_duration).clamp(MIN_REFRESH_INTERVAL, MAX_REFRESH_INTERVAL)
 }
 
-fn process_audio<T: cpal::Sample + ToPrimitive>(data: &[T], waveform_data: &Arc<Mutex<SharedVector<(f32, f32)>>>) {
-    let mut min_max_data = vec![];
+/// Rechnet eine Verstärkung in dB in einen linearen Faktor um.
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Rechnet einen linearen Pegel in dBFS um, nach unten begrenzt auf `MIN_LEVEL_DB`.
+fn gain_to_db(gain: f32) -> f32 {
+    (20.0 * gain.abs().log10()).max(MIN_LEVEL_DB)
+}
 
-    // Gruppiere alle 128 Samples und berechne Min/Max
-    // Statischer Buffer für überstehende Daten zwischen den Aufrufen
-    thread_local! {
-        static REMAINDER: RefCell<Vec<f32>> = RefCell::new(Vec::new());
+/// Kanalindizes der beiden Spuren aus der Auswahl in der UI (ab 1). Eine Auswahl jenseits der
+/// Kanäle des Geräts fällt auf den letzten Kanal zurück, bei Mono zeigen beide Spuren Kanal 0.
+fn trace_channels(a: i32, b: i32, channels: usize) -> (usize, usize) {
+    let last = channels.clamp(1, MAX_CHANNELS) - 1;
+    let index = |selection: i32| ((selection.max(1) - 1) as usize).min(last);
+    (index(a), index(b))
+}
+
+/// Ob `channel` laut Mute/Solo gezeichnet wird: gibt es Solo-Kanäle, nur diese, sonst alle nicht
+/// stummgeschalteten. `muted` und `solo` sind Bitmasken, Bit n steht für Kanal n.
+fn channel_shown(channel: usize, muted: i32, solo: i32) -> bool {
+    let bit = 1 << channel;
+    if solo != 0 {
+        solo & bit != 0
+    } else {
+        muted & bit == 0
     }
+}
 
-    // Konvertiere eingehende Daten in f32
-    let mut samples: Vec<f32> = data.iter().filter_map(|&s| s.to_f32()).collect();
+/// Welche der beiden Spuren laut Mute/Solo in der UI ausgeblendet sind.
+fn hidden_lanes(ui: &AppWindow, stream_info: StreamInfo) -> [bool; 2] {
+    let (a, b) = trace_channels(ui.get_trace_a_channel(), ui.get_trace_b_channel(), stream_info.channels);
+    let (muted, solo) = (ui.get_muted_channels(), ui.get_solo_channels());
+    [!channel_shown(a, muted, solo), !channel_shown(b, muted, solo)]
+}
 
-    // Füge evtl. übrig gebliebene Samples vom letzten Aufruf vorne an
-    REMAINDER.with(|rem| {
-        let mut rem = rem.borrow_mut();
-        if !rem.is_empty() {
-            let mut new_samples = Vec::with_capacity(rem.len() + samples.len());
-            new_samples.extend_from_slice(&rem);
-            new_samples.extend_from_slice(&samples);
-            samples.clear();
-            samples.extend(new_samples);
-            rem.clear();
+/// Größter Wert der Kanäle `a` und `b` in `points` in dBFS.
+fn peak_levels_db<'a>(points: impl IntoIterator<Item = &'a WaveformPoint>, (a, b): (usize, usize)) -> (f32, f32) {
+    let (left, right) = points.into_iter().fold((0.0f32, 0.0f32), |(left, right), point| (left.max(point.level(a)), right.max(point.level(b))));
+    (gain_to_db(left), gain_to_db(right))
+}
+
+/// Zeigt den Pegel der neuen Punkte an und erhöht den gehaltenen Maximalwert, den die UI beim
+/// Anklicken zurücksetzt.
+fn show_levels<'a>(ui: &AppWindow, points: impl IntoIterator<Item = &'a WaveformPoint>, stream_info: StreamInfo) {
+    let (left, right) = peak_levels_db(points, trace_channels(ui.get_trace_a_channel(), ui.get_trace_b_channel(), stream_info.channels));
+    ui.set_level_l_db(left);
+    ui.set_level_r_db(right);
+    ui.set_level_max_l_db(ui.get_level_max_l_db().max(left));
+    ui.set_level_max_r_db(ui.get_level_max_r_db().max(right));
+}
+
+/// Integriert die neuen Punkte der angezeigten Kanäle in `vu_meter` und zeigt das Ergebnis als
+/// Pegelbalken an. Jeder Punkt zählt mit der Dauer eines Chunks.
+fn show_vu_meter<'a>(ui: &AppWindow, vu_meter: &mut VuMeter, points: impl IntoIterator<Item = &'a WaveformPoint>, stream_info: StreamInfo) {
+    let (a, b) = trace_channels(ui.get_trace_a_channel(), ui.get_trace_b_channel(), stream_info.channels);
+    let step = chunk_duration(ui.get_chunk_size(), stream_info.channels as i32, stream_info.sample_rate as i32);
+    let integration = if ui.get_vu_slow() { vu_meter::SLOW } else { vu_meter::FAST };
+    for point in points {
+        vu_meter.push((point.level(a), point.level(b)), step, integration);
+    }
+    let (left, right) = vu_meter.levels_db(MIN_LEVEL_DB);
+    ui.set_vu_l_db(left);
+    ui.set_vu_r_db(right);
+}
+
+/// Zeigt an, ob in den neuen Punkten der angezeigten Kanäle ein Signal liegt. Ohne neue Punkte
+/// (z.B. bei einem hängenden Stream) geht die Anzeige nach `signal_detect::RELEASE` aus.
+fn show_signal_presence<'a>(ui: &AppWindow, detector: &mut SignalDetector, points: impl IntoIterator<Item = &'a WaveformPoint>, stream_info: StreamInfo, elapsed: Duration) {
+    let (a, b) = trace_channels(ui.get_trace_a_channel(), ui.get_trace_b_channel(), stream_info.channels);
+    let level = points.into_iter().fold(0.0f32, |level, point| level.max(point.level(a)).max(point.level(b)));
+    ui.set_signal_present(detector.update(level, elapsed));
+}
+
+/// Misst während einer laufenden Kalibrierung den Pegel der angezeigten Kanäle und zeigt die
+/// restliche Zeit an. Danach wird die feste Verstärkung auf den Spitzenwert eingestellt.
+fn calibrate_gain<'a>(ui: &AppWindow, calibration: &mut Option<Calibration>, points: impl IntoIterator<Item = &'a WaveformPoint>, stream_info: StreamInfo, elapsed: Duration) {
+    let Some(measurement) = calibration.as_mut() else {
+        return;
+    };
+    let (a, b) = trace_channels(ui.get_trace_a_channel(), ui.get_trace_b_channel(), stream_info.channels);
+    let level = points.into_iter().fold(0.0f32, |level, point| level.max(point.level(a)).max(point.level(b)));
+    let Some(peak) = measurement.update(level, elapsed) else {
+        ui.set_calibration_countdown(measurement.remaining().as_secs_f32().ceil() as i32);
+        return;
+    };
+    *calibration = None;
+    ui.set_calibration_countdown(0);
+    match calibration::gain_db(peak, calibration::REFERENCE_DB, amplitude_scale(ui)) {
+        Some(gain_db) => {
+            // Auf den Bereich des Reglers begrenzt, damit er die eingestellte Verstärkung zeigt
+            let gain_db = gain_db.round().clamp(MIN_GAIN_DB, MAX_GAIN_DB);
+            ui.set_scale_mode(0);
+            ui.set_gain_db(gain_db);
+            ui.set_calibration_status(format!("Peak {:.1} dBFS, gain {} dB", gain_to_db(peak), gain_db).into());
+        }
+        None => ui.set_calibration_status("No signal to calibrate".into()),
+    }
+}
+
+/// Prüft, ob `path` eine lesbare WAV-Datei ist. Gelesen wird nur der Header, die Samples lädt
+/// erst `WavFile::open`.
+fn check_dropped_file(path: &Path) -> Result<(), String> {
+    let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+    if !path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("wav")) {
+        return Err(format!("{} is not a WAV file", name));
+    }
+    hound::WavReader::open(path).map(drop).map_err(|err| format!("Cannot open {}: {}", name, err))
+}
+
+/// Zeigt `message` für `TOAST_DURATION` über dem Fenster. Eine neue Meldung ersetzt die alte
+/// und startet die Zeit neu.
+fn show_toast(ui: &AppWindow, timer: &slint::Timer, message: String) {
+    ui.set_toast(message.into());
+    let ui_weak = ui.as_weak();
+    timer.start(slint::TimerMode::SingleShot, TOAST_DURATION, move || {
+        if let Some(ui) = ui_weak.upgrade() {
+            ui.set_toast("".into());
         }
     });
+}
+
+/// Lautheit mit einer Nachkommastelle, "–" ohne Messwert oder unter dem absoluten Gate.
+fn format_lufs(value: f32) -> String {
+    if value as f64 > loudness::ABSOLUTE_GATE_LUFS {
+        format!("{:.1}", value)
+    } else {
+        "–".to_string()
+    }
+}
+
+/// Vertikale Skalierung des Verlaufs vor der Darstellung.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum VerticalScale {
+    /// Feste Verstärkung in dB
+    Gain(f32),
+    /// Vollausschlag (±1.0) füllt die Spur, unabhängig vom Inhalt
+    FullScale,
+    /// Der größte sichtbare Wert wird in jedem Frame neu auf 1.0 skaliert
+    Auto,
+    /// Wie `Auto`, die Verstärkung gleitet aber mit den Zeitkonstanten `attack` und `release`
+    SmoothAuto { attack: Duration, release: Duration },
+}
+
+/// Vertikale Skalierung laut UI.
+fn vertical_scale(ui: &AppWindow) -> VerticalScale {
+    match ui.get_scale_mode() {
+        1 => VerticalScale::FullScale,
+        2 => VerticalScale::Auto,
+        3 => VerticalScale::SmoothAuto {
+            attack: Duration::from_millis(ui.get_auto_attack_ms().max(0) as u64),
+            release: Duration::from_millis(ui.get_auto_release_ms().max(0) as u64),
+        },
+        _ => VerticalScale::Gain(ui.get_gain_db()),
+    }
+}
+
+/// Glättung der Hüllkurve laut UI, das Fenster in Punkten.
+fn envelope_smoothing(ui: &AppWindow) -> Smoothing {
+    let window = ui.get_smoothing_window().max(1) as usize;
+    match ui.get_smoothing_mode() {
+        1 => Smoothing::MovingAverage(window),
+        2 => Smoothing::Exponential(window),
+        _ => Smoothing::Off,
+    }
+}
+
+/// Faktor, mit dem die Werte vor der Darstellung multipliziert werden. Nur `Auto` hängt von den
+/// sichtbaren `traces` ab, bei `SmoothAuto` ist es das Ziel der Glättung. Die anderen Skalen
+/// bleiben fest, damit Pegel über die Zeit vergleichbar sind.
+fn display_scale(traces: &[TracePoint], scale: VerticalScale) -> f32 {
+    match scale {
+        VerticalScale::Gain(gain_db) => db_to_gain(gain_db),
+        VerticalScale::FullScale => LANE_FULL_SCALE,
+        VerticalScale::Auto | VerticalScale::SmoothAuto { .. } => {
+            let max = traces.iter().fold(0.0f32, |max, &(left, right)| max.max(left).max(right));
+            if max > 0.0 { 1.0 / max } else { 1.0 }
+        }
+    }
+}
+
+/// Übernimmt alle neuen Punkte aus dem Audio-Thread in den Verlauf und behält nur die
+/// letzten `history_len` Punkte. Wird `history_len` verkleinert, wird sofort gekürzt.
+/// Liefert die Anzahl übernommener Punkte.
+fn receive_points(consumer: &mut Consumer<WaveformPoint>, waveform: &mut RingBuffer<WaveformPoint>, history_len: usize, meters: &Meters) -> usize {
+    waveform.set_capacity(history_len);
+    // Nach einem Hänger der UI würden die ältesten wartenden Punkte ohnehin sofort überschrieben.
+    // Sie werden ungelesen verworfen und gezählt, damit die Queue schnell wieder Platz hat.
+    let excess = consumer.slots().saturating_sub(history_len.max(1));
+    if let Ok(chunk) = consumer.read_chunk(excess) {
+        chunk.commit_all();
+        meters.drop_points(excess);
+    }
+    let mut received = 0;
+    while let Ok(point) = consumer.pop() {
+        received += 1;
+        waveform.push(point);
+    }
+    received
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmnc::{AudioSource, Pipeline};
+
+    #[test]
+    fn scaled_plot_keeps_premultiplied_colors() {
+        let color = |a, r, g, b| slint::Color::from_argb_u8(a, r, g, b);
+        let style = PlotStyle {
+            name: "Test".into(),
+            background: color(128, 200, 100, 50),
+            trace_a: color(192, 0, 0, 255),
+            trace_b: color(192, 0, 255, 0),
+            grid: color(255, 128, 128, 128),
+            axis: color(255, 255, 255, 255),
+            peak: color(255, 255, 0, 0),
+        };
+        let options = |scale_factor| PlotOptions { scale_factor, ..PlotOptions::default() };
+        let traces = vec![(1.0, 1.0); 100];
+        let normal = render_plot::render_plot_buffer(&traces, 0, 100, 200, &style, &options(1.0));
+        let columns = render_plot::fit_to_width(&traces, 0.0, 100, 200);
+        let hidpi = render_plot::render_plot_buffer(&columns, 0, 200, 400, &style, &options(2.0));
+        assert_eq!(hidpi.dimensions(), (200, 400));
+        // Dieselbe logische Stelle hat dieselbe Farbe: Hintergrund und Mitte der oberen Spur
+        assert_eq!(normal.get_pixel(0, 0), hidpi.get_pixel(0, 0));
+        assert_eq!(normal.get_pixel(50, 50), hidpi.get_pixel(100, 100));
+        for img in [&normal, &hidpi] {
+            assert!(img.pixels().all(|pixel| pixel.0[..3].iter().all(|&channel| channel <= pixel.0[3])));
+        }
+    }
+
+    #[test]
+    fn onset_markers_follow_their_point() {
+        // Zwei Punkte je Spalte: Punkt 10 liegt bei start 4 in Spalte 3
+        assert_eq!(render_plot::point_column(10, 4.0, 200, 100), Some(3));
+        // Gestreckt beginnt Punkt 10 in Spalte 24
+        assert_eq!(render_plot::point_column(10, 4.0, 25, 100), Some(24));
+        assert_eq!(render_plot::point_column(3, 4.0, 200, 100), None);
+        assert_eq!(render_plot::point_column(300, 4.0, 200, 100), None);
+    }
+
+    #[test]
+    fn fixed_scales_ignore_the_visible_signal() {
+        let quiet = [(0.1, 0.05)];
+        let loud = [(0.8, 0.4)];
+        for scale in [VerticalScale::Gain(6.0), VerticalScale::FullScale] {
+            assert_eq!(display_scale(&quiet, scale), display_scale(&loud, scale));
+        }
+        // ±1.0 reicht genau bis an den Rand der Spur
+        assert_eq!(render_plot::AmplitudeScale::Linear.lane_fraction(display_scale(&quiet, VerticalScale::FullScale)), 1.0);
+        assert!((display_scale(&quiet, VerticalScale::Auto) - 10.0).abs() < 1e-6);
+        assert!((display_scale(&loud, VerticalScale::Auto) - 1.25).abs() < 1e-6);
+        assert_eq!(display_scale(&[], VerticalScale::Auto), 1.0);
+    }
+
+    #[test]
+    fn peak_levels_are_reported_in_dbfs() {
+        let mut loud = [0.0; MAX_CHANNELS];
+        loud[0] = 0.5;
+        loud[1] = 1.0;
+        let mut quiet = [0.0; MAX_CHANNELS];
+        quiet[0] = 0.25;
+        let (loud, quiet) = (WaveformPoint::from_levels(loud), WaveformPoint::from_levels(quiet));
+        let (left, right) = peak_levels_db(&[quiet, loud, quiet], (0, 1));
+        assert!((left - -6.0206).abs() < 1e-3, "{}", left);
+        assert!(right.abs() < 1e-6, "{}", right);
+        // Stille landet auf der Untergrenze
+        assert_eq!(peak_levels_db(&[WaveformPoint::default()], (0, 1)), (MIN_LEVEL_DB, MIN_LEVEL_DB));
+    }
+
+    #[test]
+    fn trace_channels_stay_within_device_channels() {
+        assert_eq!(trace_channels(1, 2, 2), (0, 1));
+        assert_eq!(trace_channels(3, 4, 4), (2, 3));
+        // Mono zeigt Kanal 0 doppelt, zu große oder ungültige Auswahl fällt auf vorhandene Kanäle
+        assert_eq!(trace_channels(1, 2, 1), (0, 0));
+        assert_eq!(trace_channels(5, 6, 4), (3, 3));
+        assert_eq!(trace_channels(0, -1, 2), (0, 0));
+        assert_eq!(trace_channels(12, 12, 16), (MAX_CHANNELS - 1, MAX_CHANNELS - 1));
+    }
+
+    #[test]
+    fn trigger_finds_latest_edge_with_enough_samples() {
+        use trigger::find_trigger;
+        // Sägezahn mit Periode 10: steigt von -0.5 bis 0.4, fällt dann auf -0.5
+        let saw: Vec<f32> = (0..100).map(|i| (i % 10) as f32 / 10.0 - 0.5).collect();
+        // Steigend durch 0.0 bei Index 5, 15, ..., 95; danach müssen noch 20 Samples folgen
+        assert_eq!(find_trigger(&saw, 20, 0.0, Edge::Rising), Some(75));
+        // Fallend durch 0.0 bei jedem Periodenbeginn
+        assert_eq!(find_trigger(&saw, 20, 0.0, Edge::Falling), Some(80));
+        assert_eq!(find_trigger(&saw, 20, 0.9, Edge::Rising), None);
+        assert_eq!(find_trigger(&saw, 200, 0.0, Edge::Rising), None);
+    }
+
+    #[test]
+    fn scope_holds_last_frame_without_trigger_in_normal_mode() {
+        let mut scope = Scope::new(1000);
+        let normal = TriggerSettings { level: 0.0, edge: Edge::Rising, mode: TriggerMode::Normal };
+        let sine: Vec<f32> = (0..200).map(|i| (i as f32 * 0.2).sin()).collect();
+        scope.push_samples(&sine);
+        assert!(scope.update(50, normal));
+        let frame = scope.frame().to_vec();
+        assert_eq!(frame.len(), 50);
+        assert!(frame[0] >= 0.0 && frame[0] < 0.2, "{}", frame[0]);
+        // Stille löst nicht aus: Normal hält das Bild, Auto zeigt die neuesten Samples
+        scope.push_samples(&[0.0; 1000]);
+        assert!(!scope.update(50, normal));
+        assert_eq!(scope.frame(), frame.as_slice());
+        assert!(!scope.update(50, TriggerSettings { mode: TriggerMode::Auto, ..normal }));
+        assert!(scope.frame().iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn reconnect_delay_doubles_up_to_the_maximum() {
+        let mut reconnect = Reconnect::new();
+        assert_eq!(reconnect.delay, RECONNECT_INITIAL_DELAY);
+        reconnect.failed();
+        assert_eq!(reconnect.delay, RECONNECT_INITIAL_DELAY * 2);
+        for _ in 0..10 {
+            reconnect.failed();
+        }
+        assert_eq!(reconnect.delay, RECONNECT_MAX_DELAY);
+    }
+
+    #[test]
+    fn refresh_interval_follows_chunk_rate_within_limits() {
+        let ms = Duration::from_millis;
+        // Ein Chunk mit 2048 Samples Stereo bei 48 kHz dauert gut 21 ms
+        let chunk = chunk_duration(2048, 2, 48000);
+        assert_eq!(refresh_interval(ms(50), chunk), ms(50));
+        assert_eq!(refresh_interval(ms(10), chunk), chunk);
+        assert_eq!(refresh_interval(ms(1), Duration::ZERO), MIN_REFRESH_INTERVAL);
+        assert_eq!(refresh_interval(ms(2000), chunk), MAX_REFRESH_INTERVAL);
+        assert_eq!(chunk_duration(2048, 0, 48000), Duration::ZERO);
+    }
+
+    #[test]
+    fn view_start_pins_newest_point_to_right_edge() {
+        assert_eq!(view_start(3000, 1000), 2000);
+        assert_eq!(view_start(1000, 1000), 0);
+        // Weniger Punkte als Spalten: rechtsbündig, links bleibt Platz
+        assert_eq!(view_start(200, 1000), -800);
+        assert_eq!(view_start(0, 640), -640);
+    }
+
+    #[test]
+    fn pan_is_clamped_to_available_history() {
+        assert_eq!(clamp_pan(-5, 3000, 1000), 0);
+        assert_eq!(clamp_pan(500, 3000, 1000), 500);
+        assert_eq!(clamp_pan(5000, 3000, 1000), 2000);
+        // Weniger Verlauf als sichtbar: nichts zu verschieben
+        assert_eq!(clamp_pan(100, 200, 1000), 0);
+    }
 
-    // Verarbeite nur vollständige Chunks
-    let chunk_size = 2048;
-    let full_chunks = samples.len() / chunk_size;
-    for chunk in samples.chunks(chunk_size).take(full_chunks) {
-        let left_channel = chunk.iter().step_by(2); // Linker Kanal
-        let right_channel = chunk.iter().skip(1).step_by(2); // Rechter Kanal
+    #[test]
+    fn headless_arguments_are_validated() {
+        let cli = Cli::try_parse_from(["rmnc", "--headless", "--duration", "2.5"]).unwrap();
+        assert!(cli.headless);
+        assert_eq!(cli.duration, Some(2.5));
+        assert!(Cli::try_parse_from(["rmnc", "--headless", "--duration", "0"]).is_err());
+        assert!(Cli::try_parse_from(["rmnc", "--headless", "--duration", "abc"]).is_err());
+        // Eine Ausgabedatei ergibt nur ohne Fenster Sinn
+        assert!(Cli::try_parse_from(["rmnc", "--output", "out.csv"]).is_err());
+    }
+
+    #[test]
+    fn settings_fall_back_to_defaults() {
+        assert_eq!(Settings::parse("").unwrap(), Settings::default());
+        // Fehlende Felder behalten ihren Standardwert
+        let settings = Settings::parse("device = \"USB Audio\"\nchunk_size = 512\n").unwrap();
+        assert_eq!(settings.device.as_deref(), Some("USB Audio"));
+        assert_eq!(settings.chunk_size, 512);
+        assert_eq!(settings.history_len, DEFAULT_HISTORY_LEN);
+        assert!(Settings::parse("chunk_size = \"big\"").is_err());
+        assert!(Settings::parse("not toml at all").is_err());
+
+        let round_trip = Settings { device: Some("Mic".into()), gain_db: 6.0, ..Settings::default() };
+        assert_eq!(Settings::parse(&toml::to_string(&round_trip).unwrap()).unwrap(), round_trip);
+    }
+
+    #[test]
+    fn stream_arguments_are_parsed() {
+        let cli = Cli::try_parse_from(["rmnc"]).unwrap();
+        assert_eq!(cli.device, None);
+        assert_eq!(cli.chunk_size, None);
+        assert_eq!(cli.duration, None);
+
+        let cli = Cli::try_parse_from(["rmnc", "--device", "USB Audio", "--sample-rate", "44100", "--chunk-size", "512"]).unwrap();
+        assert_eq!(cli.device.as_deref(), Some("USB Audio"));
+        assert_eq!(cli.sample_rate, Some(44100));
+        assert_eq!(cli.chunk_size, Some(512));
+
+        assert!(Cli::try_parse_from(["rmnc", "--sample-rate", "0"]).is_err());
+        assert!(Cli::try_parse_from(["rmnc", "--sample-rate", "fast"]).is_err());
+        assert!(Cli::try_parse_from(["rmnc", "--chunk-size", "0"]).is_err());
+        assert!(Cli::try_parse_from(["rmnc", "--chunk-size", "1000000"]).is_err());
+    }
+
+    #[test]
+    fn duration_is_converted_to_points() {
+        // 2048 Samples Stereo bei 48 kHz: gut 21 ms je Punkt, 5 s also 235 Punkte
+        assert_eq!(points_for_duration(5.0, chunk_duration(2048, 2, 48000)), Some(235));
+        assert_eq!(points_for_duration(5.0, chunk_duration(1024, 2, 48000)), Some(469));
+        assert_eq!(points_for_duration(0.0, chunk_duration(2048, 2, 48000)), Some(1));
+        assert_eq!(points_for_duration(5.0, Duration::ZERO), None);
+    }
 
-        let min_left = left_channel.clone().fold(f32::INFINITY, |a, &b| f32::min(a, b));
-        let max_left = left_channel.clone().fold(f32::NEG_INFINITY, |a, &b| f32::max(a, b));
+    #[test]
+    fn zoom_changes_visible_points() {
+        assert_eq!(visible_points(1000, 1.0), 1000);
+        assert_eq!(visible_points(1000, 4.0), 250);
+        assert_eq!(visible_points(1000, 0.25), 4000);
+        assert_eq!(visible_points(1, 16.0), 1);
+    }
 
-        let min_right = right_channel.clone().fold(f32::INFINITY, |a, &b| f32::min(a, b));
-        let max_right = right_channel.clone().fold(f32::NEG_INFINITY, |a, &b| f32::max(a, b));
+    #[test]
+    fn history_stays_bounded_without_reallocating() {
+        let history_len = 100;
+        let (mut producer, mut consumer) = rtrb::RingBuffer::<WaveformPoint>::new(POINT_QUEUE_CAPACITY);
+        let mut waveform = RingBuffer::new(history_len);
+        // Anfang des Speichers: bei umgelaufenem Puffer beginnt dort der neuere Abschnitt
+        let storage = |waveform: &RingBuffer<WaveformPoint>| {
+            let (older, newer) = waveform.as_slices();
+            if newer.is_empty() { older.as_ptr() } else { newer.as_ptr() }
+        };
+        let allocation = storage(&waveform);
+
+        for cycle in 0..1000 {
+            for _ in 0..7 {
+                producer.push(WaveformPoint::from_levels([cycle as f32; MAX_CHANNELS])).unwrap();
+            }
+            receive_points(&mut consumer, &mut waveform, history_len, &Meters::new());
+            assert!(waveform.len() <= history_len);
+            assert_eq!(storage(&waveform), allocation);
+        }
+        assert_eq!(waveform.len(), history_len);
+        assert_eq!(waveform.iter().next_back().unwrap().level(0), 999.0);
 
-        // Berechne die größte Abweichung von 0 für den linken Kanal
-        let max_deviation_left = if min_left.abs() > max_left.abs() { min_left.abs() } else { max_left.abs() };
-        // Berechne die größte Abweichung von 0 für den rechten Kanal
-        let max_deviation_right = if min_right.abs() > max_right.abs() { min_right.abs() } else { max_right.abs() };
-        min_max_data.push((max_deviation_left,max_deviation_right)); // Linker Kanal (nach oben)
+        // Verkleinern kürzt sofort
+        receive_points(&mut consumer, &mut waveform, 10, &Meters::new());
+        assert_eq!(waveform.len(), 10);
     }
 
-    // Überstehende Samples für den nächsten Aufruf zwischenspeichern
-    let remainder = samples.len() % chunk_size;
-    if remainder > 0 {
-        REMAINDER.with(|rem| {
-            rem.borrow_mut().extend_from_slice(&samples[samples.len() - remainder..]);
+    /// Deckende Farben, damit jedes Pixel genau Hintergrund- oder Spurfarbe hat
+    fn opaque_style() -> PlotStyle {
+        let color = |r, g, b| slint::Color::from_rgb_u8(r, g, b);
+        PlotStyle {
+            name: "Test".into(),
+            background: color(0, 0, 0),
+            trace_a: color(255, 0, 0),
+            trace_b: color(0, 255, 0),
+            grid: color(128, 128, 128),
+            axis: color(255, 255, 255),
+            peak: color(0, 0, 255),
+        }
+    }
+
+    /// Je Pixelzeile einer Spalte: 0 Hintergrund, 1 obere Spur, 2 untere Spur
+    fn plot_column(img: &image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, x: u32) -> Vec<u8> {
+        (0..img.height())
+            .map(|y| match img.get_pixel(x, y).0 {
+                [0, 0, 0, 255] => 0,
+                [255, 0, 0, 255] => 1,
+                [0, 255, 0, 255] => 2,
+                other => panic!("unexpected pixel {:?} at ({}, {})", other, x, y),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn plot_columns_stay_inside_their_lane() {
+        let style = opaque_style();
+        let options = PlotOptions::default();
+        // 4 x 8 Pixel: obere Spur Zeilen 0..4 um y = 2, untere Spur Zeilen 4..8 um y = 6
+        let traces = [(LANE_FULL_SCALE, 0.0), (1.0, LANE_FULL_SCALE), (10.0, 0.0)];
+        let img = render_plot::render_plot_buffer(&traces, 0, 4, 8, &style, &options);
+        // Genau am Rand füllt die Spur ihre Hälfte, ohne in die andere zu reichen
+        assert_eq!(plot_column(&img, 0), [1, 1, 1, 1, 0, 0, 2, 0]);
+        assert_eq!(plot_column(&img, 1), [0, 1, 1, 0, 2, 2, 2, 2]);
+        // Übersteuerte Werte werden am Rand abgeschnitten
+        assert_eq!(plot_column(&img, 2), plot_column(&img, 0));
+        assert_eq!(plot_column(&img, 3), [0; 8]);
+
+        // Punkt `idx` landet in Spalte `idx - start`, Punkte außerhalb werden übersprungen
+        let shifted = render_plot::render_plot_buffer(&traces, -1, 4, 8, &style, &options);
+        assert_eq!(plot_column(&shifted, 0), [0; 8]);
+        assert_eq!(plot_column(&shifted, 1), plot_column(&img, 0));
+        let scrolled = render_plot::render_plot_buffer(&traces, 2, 4, 8, &style, &options);
+        assert_eq!(plot_column(&scrolled, 0), plot_column(&img, 2));
+        let long = vec![(LANE_FULL_SCALE, LANE_FULL_SCALE); 10];
+        let clipped = render_plot::render_plot_buffer(&long, 0, 4, 8, &style, &options);
+        assert_eq!(plot_column(&clipped, 3), [1, 1, 1, 1, 2, 2, 2, 2]);
+
+        // 0 dBFS füllt auch in der dB-Skala genau die Spur, unter dem Boden bleibt die Grundlinie
+        let db = PlotOptions { scale: AmplitudeScale::Db { floor_db: -60.0 }, ..options };
+        let img = render_plot::render_plot_buffer(&[(1.0, 1e-4)], 0, 1, 8, &style, &db);
+        assert_eq!(plot_column(&img, 0), [1, 1, 1, 1, 0, 0, 2, 0]);
+    }
+
+    #[test]
+    fn silent_traces_render_without_scaling() {
+        let silence = [(0.0, 0.0); 4];
+        // Ohne Signal fällt die automatische Skalierung auf 1.0 zurück statt durch 0 zu teilen
+        let scale = display_scale(&silence, VerticalScale::Auto);
+        assert_eq!(scale, 1.0);
+        let traces: Vec<TracePoint> = silence.iter().map(|&(left, right)| (left * scale, right * scale)).collect();
+        let options = PlotOptions { fill: TraceFill::Gradient, ..PlotOptions::default() };
+        let img = render_plot::render_plot_buffer(&traces, 0, 5, 8, &opaque_style(), &options);
+        // Stille erscheint als Grundlinie je Spur, die Spalte ohne Punkt bleibt leer
+        assert!((0..4).all(|x| plot_column(&img, x) == [0, 0, 1, 0, 0, 0, 2, 0]));
+        assert_eq!(plot_column(&img, 4), [0; 8]);
+    }
+
+    #[test]
+    fn signal_detection_has_hysteresis() {
+        let frame = Duration::from_millis(100);
+        let mut detector = SignalDetector::new();
+        assert!(!detector.update(0.0, frame));
+        // -54 dBFS schaltet ein, -63 dBFS liegt in der Hysterese und hält die Anzeige
+        assert!(detector.update(0.002, frame));
+        assert!(detector.update(0.0007, frame));
+        // Unter -66 dBFS geht sie erst nach `RELEASE` aus
+        for _ in 0..4 {
+            assert!(detector.update(0.0, frame));
+        }
+        assert!(!detector.update(0.0, frame));
+        // Ohne Signal reicht derselbe Pegel nicht zum Einschalten
+        assert!(!detector.update(0.0007, frame));
+    }
+
+    #[test]
+    fn collapsed_columns_keep_their_range() {
+        // Vier Punkte auf zwei Spalten: je Spalte der kleinste und der größte Wert
+        let traces = [(0.5, 2.0), (1.0, 0.0), (2.0, 1.0), (0.0, 1.0)];
+        assert_eq!(render_plot::points_per_column(4, 2), 2.0);
+        assert_eq!(render_plot::fit_to_width(&traces, 0.0, 4, 2), [(1.0, 2.0), (2.0, 1.0)]);
+        assert_eq!(render_plot::column_minimum(&traces, 0.0, 4, 2), [(0.5, 0.0), (0.0, 1.0)]);
+        // Spalten ohne Punkt bleiben leer statt unendlich
+        assert_eq!(render_plot::column_minimum(&traces, -4.0, 4, 2), [(0.0, 0.0), (0.0, 0.0)]);
+
+        // Bis zum kleinsten Wert volle Farbe, darüber bis zum größten heller
+        let style = opaque_style();
+        let min = [(LANE_FULL_SCALE / 2.0, 0.0)];
+        let options = PlotOptions { column_min: Some(&min), ..PlotOptions::default() };
+        let img = render_plot::render_plot_buffer(&[(LANE_FULL_SCALE, 0.0)], 0, 1, 8, &style, &options);
+        assert_eq!(img.get_pixel(0, 1).0, [255, 0, 0, 255]);
+        assert_ne!(img.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_ne!(img.get_pixel(0, 0).0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn smooth_auto_scale_glides_with_its_time_constants() {
+        let attack = Duration::from_millis(50);
+        let release = Duration::from_secs(1);
+        let frame = Duration::from_millis(50);
+        let mut auto_scale = AutoScale::new();
+        // Der erste Frame übernimmt das Ziel
+        assert_eq!(auto_scale.update(1.0, frame, attack, release), 1.0);
+        // Ein lauter Punkt (Ziel -20 dB): nach einer Attack-Zeitkonstante 63 % des Weges
+        let gain_db = 20.0 * auto_scale.update(0.1, frame, attack, release).log10();
+        assert!((gain_db + 20.0 * (1.0 - (-1f32).exp())).abs() < 0.01, "{}", gain_db);
+        // Zurück geht es mit der langsameren Release-Zeit
+        let before = auto_scale.update(0.1, Duration::from_secs(10), attack, release);
+        let after = auto_scale.update(1.0, frame, attack, release);
+        assert!(after > before && after < 0.2, "{} -> {}", before, after);
+        // Ohne Zeitkonstante springt die Skalierung wie bei "Auto (hard)"
+        assert!((auto_scale.update(0.5, frame, Duration::ZERO, Duration::ZERO) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn envelope_smoothing_tames_spikes_but_keeps_the_level() {
+        // Spitzen in jedem zweiten Punkt, die untere Spur ist konstant
+        let spiky: Vec<TracePoint> = (0..100).map(|i| (if i % 2 == 0 { 1.0 } else { 0.0 }, 0.5)).collect();
+        let averaged = smoothing::smooth(&spiky, Smoothing::MovingAverage(2)).unwrap();
+        assert_eq!(averaged[0], (1.0, 0.5));
+        assert!(averaged[1..].iter().all(|&(a, b)| (a - 0.5).abs() < 1e-6 && (b - 0.5).abs() < 1e-6));
+        // Exponentiell: nach einem Sprung fehlt nach n Punkten noch (1 - 1/4)^n
+        let step: Vec<TracePoint> = (0..10).map(|i| if i == 0 { (0.0, 0.0) } else { (1.0, 1.0) }).collect();
+        let exponential = smoothing::smooth(&step, Smoothing::Exponential(4)).unwrap();
+        assert!((exponential[4].0 - (1.0 - 0.75f32.powi(4))).abs() < 1e-6, "{:?}", exponential[4]);
+        // Ohne Glättung bleiben die Punkte, wie sie sind
+        assert_eq!(smoothing::smooth(&spiky, Smoothing::Off), None);
+        assert_eq!(smoothing::smooth(&spiky, Smoothing::MovingAverage(1)), None);
+    }
+
+    /// Quelle ohne Gerät: gibt vorgefertigte interleavte Samples in wechselnden Blockgrößen an die
+    /// Pipeline, wie ein Gerät, das unterschiedlich große Puffer liefert.
+    struct SignalSource {
+        info: StreamInfo,
+        samples: Vec<f32>,
+        /// Frames je Puffer, wird zyklisch wiederholt
+        block_frames: Vec<usize>,
+    }
+
+    impl AudioSource for SignalSource {
+        type Running = ();
+
+        fn info(&self) -> StreamInfo {
+            self.info
+        }
+
+        fn start<F: FnMut(WaveformPoint) + Send + 'static>(self, mut pipeline: Pipeline<F>) -> Result<(), AudioError> {
+            let mut offset = 0;
+            for &frames in self.block_frames.iter().cycle() {
+                if offset >= self.samples.len() {
+                    break;
+                }
+                let end = (offset + frames * self.info.channels).min(self.samples.len());
+                pipeline.process(&self.samples[offset..end], None);
+                offset = end;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn synthetic_source_runs_through_the_whole_pipeline() {
+        // Eine Sekunde bei 44,1 kHz, intern 48 kHz: links Sinus, rechts leises Rauschen, dahinter Stille
+        let channels = 4;
+        let device_rate = 44100;
+        let mut noise_state = 0x1234_5678u32;
+        let mut samples = Vec::with_capacity(device_rate as usize * channels);
+        for n in 0..device_rate as usize {
+            noise_state = noise_state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (noise_state >> 8) as f32 / (1 << 24) as f32 * 0.2 - 0.1;
+            samples.extend_from_slice(&[0.8 * (2.0 * std::f32::consts::PI * 1000.0 * n as f32 / device_rate as f32).sin(), noise, 0.0, 0.0]);
+        }
+        // Der Sinc-Kern des Resamplers schwingt über, einzelne Spitzen des Rauschens liegen daher
+        // über 0,1. Obergrenze ist die größte Abweichung des für sich umgerechneten Rauschkanals.
+        let noise: Vec<f32> = samples.iter().skip(1).step_by(channels).copied().collect();
+        let mut resampled_noise = Vec::new();
+        rmnc::resample::Resampler::new(device_rate, 48000, 1).process(&noise, &mut resampled_noise);
+        let noise_peak = resampled_noise.iter().fold(0f32, |max, &s| max.max(s.abs()));
+        let info = StreamInfo { sample_rate: 48000, device_sample_rate: device_rate, requested_sample_rate: None, channels, sample_format: None, significant_bits: None, buffer_size: None, requested_buffer_size: None, resampler_latency: Duration::ZERO };
+        let source = SignalSource { info, samples, block_frames: vec![37, 512, 1001, 256] };
+
+        // 480 Frames je Punkt, also 10 ms und 100 Punkte je Sekunde
+        let params = Arc::new(Params::new(480 * channels));
+        let meters = Arc::new(Meters::new());
+        let (mut producer, mut consumer) = rtrb::RingBuffer::<WaveformPoint>::new(256);
+        let pipeline = Pipeline::new(source.info(), params, meters.clone(), None, move |point| {
+            let _ = producer.push(point);
         });
+        source.start(pipeline).unwrap();
+
+        let history_len = 80;
+        let mut waveform_data = RingBuffer::new(history_len);
+        let received = receive_points(&mut consumer, &mut waveform_data, history_len, &meters);
+        // Der Resampler hält die letzten Frames für seinen Filterkern zurück. Was nicht mehr in den
+        // Verlauf passt, wird ungelesen verworfen.
+        let produced = received + meters.dropped_points() as usize;
+        assert_eq!(received, history_len);
+        assert!((98..=100).contains(&produced), "{} points", produced);
+        assert_eq!(waveform_data.len(), history_len);
+        assert!(!meters.take_clipped());
+
+        for point in &waveform_data {
+            assert!((0.75..=0.81).contains(&point.level(0)), "sine peak {:?}", point);
+            assert!((0.03..=noise_peak + 1e-4).contains(&point.level(1)), "noise peak {:?}", point);
+            // Keine Kanäle vertauscht oder übersprochen
+            assert!(point.levels()[2..].iter().all(|&value| value == 0.0), "{:?}", point);
+        }
     }
 
-    // Aktualisiere die SharedVector-Daten
-    let mut waveform = waveform_data.lock().unwrap();
-    for value in min_max_data {
-        waveform.push(value);
+    #[test]
+    fn overlay_layout_shares_one_axis() {
+        let style = opaque_style();
+        let stacked = PlotOptions::default();
+        let overlay = PlotOptions { layout: PlotLayout::Overlay, ..stacked };
+        // Spur A halb, Spur B ganz ausgesteuert: übereinander nutzen beide die volle Höhe um y = 4
+        let traces = [(LANE_FULL_SCALE / 2.0, LANE_FULL_SCALE)];
+        let img = render_plot::render_plot_buffer(&traces, 0, 1, 8, &style, &overlay);
+        let pixels: Vec<[u8; 4]> = (0..8).map(|y| img.get_pixel(0, y).0).collect();
+        // Außen nur die halbtransparente Spur B, in der Mitte scheint Spur A durch
+        assert_eq!(pixels[0], pixels[7]);
+        assert!(pixels[0][0] == 0 && pixels[0][1] > 0 && pixels[0][1] < 255, "{:?}", pixels[0]);
+        for pixel in &pixels[2..6] {
+            assert!(pixel[0] > 0 && pixel[1] > 0, "{:?}", pixel);
+        }
+        // Gestapelt bleibt es beim bisherigen Layout
+        let img = render_plot::render_plot_buffer(&traces, 0, 1, 8, &style, &stacked);
+        assert_eq!(plot_column(&img, 0), [0, 1, 1, 0, 2, 2, 2, 2]);
     }
 
-    // Begrenze die Länge des Verlaufs (z. B. 1000 Punkte)
-    if waveform.len() > 2000 {
-        let excess = waveform.len() - 1000;
-        let new_waveform: SharedVector<(f32, f32)> = waveform[excess..].into(); // Kopiere nur die letzten 1000 Elemente
-        *waveform = new_waveform; // Ersetze den alten Vektor
+    #[test]
+    fn thick_lines_widen_columns_within_their_lane() {
+        let style = opaque_style();
+        let options = PlotOptions::default();
+        // 1 x 16 Pixel: obere Spur Zeilen 0..8 um y = 4, untere Spur Zeilen 8..16 um y = 12
+        let traces = [(0.5, LANE_FULL_SCALE)];
+        let thin = render_plot::render_plot_buffer(&traces, 0, 1, 16, &style, &options);
+        assert_eq!(plot_column(&thin, 0), [0, 0, 0, 1, 1, 0, 0, 0, 2, 2, 2, 2, 2, 2, 2, 2]);
+        // Je ein Pixel mehr an beiden Enden, die volle untere Spur reicht nicht in die obere
+        let thick = render_plot::render_plot_buffer(&traces, 0, 1, 16, &style, &PlotOptions { line_width: 3, ..options });
+        assert_eq!(plot_column(&thick, 0), [0, 0, 1, 1, 1, 1, 0, 0, 2, 2, 2, 2, 2, 2, 2, 2]);
+        // Auf HiDPI-Bildschirmen wächst die Stärke mit dem Skalierungsfaktor
+        let hidpi = render_plot::render_plot_buffer(&traces, 0, 1, 32, &style, &PlotOptions { line_width: 3, scale_factor: 2.0, ..options });
+        assert_eq!(plot_column(&hidpi, 0)[..16], [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0]);
     }
-}
 
-fn err_fn(err: cpal::StreamError) {
-    eprintln!("Stream error: {}", err);
+    #[test]
+    fn time_axis_follows_the_point_times() {
+        // 50 Punkte, eine halbe Sekunde Lücke, 50 weitere Punkte; 10 ms je Punkt
+        let times: Vec<f64> = (0..50).map(|i| i as f64 * 0.01).chain((0..50).map(|i| 1.0 + i as f64 * 0.01)).collect();
+        let ages = column_ages(&times, 0.0, 100, 100);
+        assert!(ages[99].abs() < 1e-6 && (ages[50] - 0.49).abs() < 1e-4 && (ages[49] - 1.0).abs() < 1e-4, "{:?}", ages);
+        assert!(column_ages(&times, -10.0, 100, 100)[..10].iter().all(|age| age.is_nan()));
+
+        let time_axis = TimeAxis { sample_rate: 48000, chunk_size: 480, channels: 1, points_per_column: 1.0, pan_points: 0, column_ages: Some(&ages) };
+        let options = PlotOptions { time_axis: Some(time_axis), ..PlotOptions::default() };
+        let img = render_plot::render_plot_buffer(&[(0.0, 0.0); 100], 0, 100, 40, &opaque_style(), &options);
+        // Markierungen unter der Achse bei Zeile 24: 0 s ganz rechts, -1 s vor der Lücke statt
+        // links außerhalb wie bei gleichmäßigen Abständen
+        let ticks: Vec<u32> = (0..100).filter(|&x| img.get_pixel(x, 25).0 == [255, 255, 255, 255]).collect();
+        assert_eq!(ticks.len(), 2, "{:?}", ticks);
+        assert!((48..=49).contains(&ticks[0]) && ticks[1] == 99, "{:?}", ticks);
+    }
+
+    #[test]
+    fn stalled_display_drops_the_oldest_points() {
+        let meters = Meters::new();
+        let (mut producer, mut consumer) = rtrb::RingBuffer::<WaveformPoint>::new(16);
+        let mut waveform = RingBuffer::new(5);
+        for idx in 0..12 {
+            producer.push(WaveformPoint::from_levels([idx as f32; MAX_CHANNELS])).unwrap();
+        }
+        // Nur die neuesten fünf passen in den Verlauf, die übrigen werden ungelesen verworfen
+        assert_eq!(receive_points(&mut consumer, &mut waveform, 5, &meters), 5);
+        assert_eq!(waveform.iter().map(|point| point.level(0)).collect::<Vec<_>>(), [7.0, 8.0, 9.0, 10.0, 11.0]);
+        assert_eq!(meters.dropped_points(), 7);
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn mute_and_solo_pick_the_drawn_channels() {
+        assert!(channel_shown(0, 0, 0) && channel_shown(7, 0, 0));
+        assert!(!channel_shown(1, 0b10, 0) && channel_shown(0, 0b10, 0));
+        // Solo hat Vorrang und blendet alle übrigen aus, auch stummgeschaltete Solo-Kanäle bleiben sichtbar
+        assert!(channel_shown(2, 0b100, 0b100) && !channel_shown(0, 0, 0b100));
+
+        let options = PlotOptions { hidden: [false, true], ..PlotOptions::default() };
+        let img = render_plot::render_plot_buffer(&[(1.0, 1.0); 4], 0, 4, 8, &opaque_style(), &options);
+        // Nur die obere Spur, die untere Hälfte bleibt Hintergrund
+        assert_eq!(img.get_pixel(1, 2).0, [255, 0, 0, 255]);
+        assert!((4..8).all(|y| img.get_pixel(1, y).0 == [0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn held_view_stays_on_its_points() {
+        // Live folgt die Ansicht dem neuesten Punkt, egal wie weit sie verschoben war
+        assert_eq!(view_pan(300, 5, true, 5000, 1000), 0);
+        // Gehalten wandert sie mit jedem neuen Punkt weiter in die Vergangenheit
+        assert_eq!(view_pan(300, 5, false, 5000, 1000), 305);
+        assert_eq!(view_pan(300, 0, false, 5000, 1000), 300);
+        // Fallen alte Punkte aus dem vollen Verlauf, bleibt sie am ältesten Punkt
+        assert_eq!(view_pan(3990, 20, false, 5000, 1000), 4000);
+        assert_eq!(view_pan(0, 20, false, 500, 1000), 0);
+    }
+
+    #[test]
+    fn dropped_files_must_be_readable_wav_files() {
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        assert!(check_dropped_file(&dir.join("notes.txt")).unwrap_err().contains("not a WAV file"));
+
+        let broken = dir.join(format!("rmnc-drop-test-broken-{}.wav", id));
+        std::fs::write(&broken, b"not audio").unwrap();
+        let result = check_dropped_file(&broken);
+        std::fs::remove_file(&broken).unwrap();
+        assert!(result.unwrap_err().starts_with("Cannot open"));
+
+        let spec = hound::WavSpec { channels: 2, sample_rate: 48000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let valid = dir.join(format!("rmnc-drop-test-{}.WAV", id));
+        let mut writer = hound::WavWriter::create(&valid, spec).unwrap();
+        writer.write_sample(0i16).unwrap();
+        writer.write_sample(0i16).unwrap();
+        writer.finalize().unwrap();
+        let result = check_dropped_file(&valid);
+        std::fs::remove_file(&valid).unwrap();
+        assert_eq!(result, Ok(()));
+    }
+
+    /// Schiebt je Bild `per_frame` fortlaufend nummerierte Punkte durch einen Verlauf mit
+    /// `history_len` Punkten und liefert für jedes Bild den Verlauf und den linken Rand wie im
+    /// Live-Betrieb.
+    fn scripted_scroll(history_len: usize, per_frame: u64, frames: u64, visible_len: usize, width: u32) -> Vec<(Vec<u64>, f64)> {
+        let mut waveform = RingBuffer::new(history_len);
+        (0..frames)
+            .map(|frame| {
+                for index in frame * per_frame..(frame + 1) * per_frame {
+                    waveform.push(index);
+                }
+                let start = render_plot::aligned_start(view_start(waveform.len(), visible_len), waveform.first_index(), visible_len, width);
+                (waveform.iter().copied().collect(), start)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scrolling_keeps_points_in_the_same_columns_across_the_wrap() {
+        // 40 Punkte auf 15 Spalten, also 8 Punkte je 3 Spalten; der Verlauf läuft nach 50 Punkten um
+        let (visible_len, width) = (40, 15);
+        let grid: Vec<f64> = (0..100).map(|k| (k * visible_len / width as usize) as f64).collect();
+        for (indices, start) in scripted_scroll(50, 3, 40, visible_len, width) {
+            let times: Vec<f64> = indices.iter().map(|&index| index as f64).collect();
+            let firsts = render_plot::column_times(&times, start, visible_len, width);
+            // Jede Spalte beginnt an einem festen Punkt, der Verlauf rückt nur um ganze Spalten
+            assert!(firsts.iter().filter(|first| !first.is_nan()).all(|first| grid.contains(first)), "{:?}", firsts);
+            // Der neueste Punkt liegt in der letzten Spalte
+            let newest = *times.last().unwrap();
+            assert_eq!(firsts[width as usize - 1], *grid.iter().rev().find(|&&first| first <= newest).unwrap());
+        }
+    }
+
+    /// Bildstreifen zum Anschauen: je Bild eine Zeile des Verlaufs, das neueste Bild unten.
+    /// Impulse alle 16 Punkte müssen gerade, gleich breite Diagonalen ergeben, auch ab Bild 60,
+    /// wo der Verlauf umläuft. `cargo test scroll_filmstrip -- --ignored --nocapture` schreibt
+    /// den Streifen ins Temp-Verzeichnis.
+    #[test]
+    #[ignore]
+    fn scroll_filmstrip() {
+        let (visible_len, width, row_height) = (320, 120, 12);
+        let options = PlotOptions::default();
+        let frames = scripted_scroll(300, 5, 120, visible_len, width);
+        let mut strip = image::RgbaImage::new(width, row_height * frames.len() as u32);
+        for (row, (indices, start)) in frames.iter().enumerate() {
+            let traces: Vec<TracePoint> = indices.iter().map(|&index| if index % 16 == 0 { (LANE_FULL_SCALE, LANE_FULL_SCALE) } else { (0.3, 0.3) }).collect();
+            let columns = render_plot::fit_to_width(&traces, *start, visible_len, width);
+            let frame = render_plot::render_plot_buffer(&columns, 0, width, row_height, &opaque_style(), &options);
+            image::imageops::replace(&mut strip, &frame, 0, row as i64 * row_height as i64);
+        }
+        let path = std::env::temp_dir().join("rmnc-scroll-filmstrip.png");
+        strip.save(&path).unwrap();
+        println!("{}", path.display());
+    }
+
+    #[test]
+    fn signed_envelope_is_drawn_per_lane() {
+        // Je Spalte der kleinste der kleinsten und der größte der größten Werte
+        let envelope = Envelope { lows: vec![(-0.5, 0.1), (-0.2, 0.3)], highs: vec![(0.4, 0.2), (0.9, 0.5)] };
+        assert_eq!(render_plot::fit_envelope(&envelope, 0.0, 2, 1), Envelope { lows: vec![(-0.5, 0.1)], highs: vec![(0.9, 0.5)] });
+
+        // Spur A nur über, Spur B nur unter der Mittellinie statt symmetrisch
+        let envelope = Envelope { lows: vec![(0.0, -LANE_FULL_SCALE)], highs: vec![(LANE_FULL_SCALE, 0.0)] };
+        let options = PlotOptions { envelope: Some(&envelope), ..PlotOptions::default() };
+        let img = render_plot::render_plot_buffer(&[(LANE_FULL_SCALE, LANE_FULL_SCALE)], 0, 1, 8, &opaque_style(), &options);
+        assert_eq!(plot_column(&img, 0), [1, 1, 0, 0, 0, 0, 2, 2]);
+        let symmetric = render_plot::render_plot_buffer(&[(LANE_FULL_SCALE, LANE_FULL_SCALE)], 0, 1, 8, &opaque_style(), &PlotOptions { envelope: None, ..options });
+        assert_eq!(plot_column(&symmetric, 0), [1, 1, 1, 1, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn gaps_are_drawn_as_markers() {
+        let gap_columns = [1];
+        let options = PlotOptions { gaps: &gap_columns, ..PlotOptions::default() };
+        let img = render_plot::render_plot_buffer(&[(0.0, 0.0); 3], 0, 3, 8, &opaque_style(), &options);
+        // Senkrechte Linie in der Farbe der Spitzenwerte über die ganze Höhe
+        assert!([1, 6].iter().all(|&y| img.get_pixel(1, y).0 == [0, 0, 255, 255]));
+        assert_eq!(img.get_pixel(0, 1).0, [0, 0, 0, 255]);
+    }
 }
@@ -0,0 +1,104 @@
+// Noise-Gate vor der Reduktion, damit ein überwiegend stiller Raum eine ruhige Anzeige ergibt.
+// Samples unter der Schwelle werden zu 0. Nach dem letzten Sample über der Schwelle bleibt das
+// Gate noch eine Haltezeit offen und schließt dann über die Release-Zeit, damit es bei Signalen
+// um die Schwelle nicht flattert. Der Zustand bleibt je Kanal zwischen den Aufrufen erhalten.
+
+use std::time::Duration;
+
+/// Einstellungen des Gates, für eine feste Abtastrate in Frames umgerechnet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GateSettings {
+    /// Betrag, ab dem das Gate öffnet
+    pub threshold: f32,
+    /// Frames, die das Gate nach dem letzten Sample über der Schwelle voll offen bleibt
+    pub hold_frames: u32,
+    /// Frames, über die die Verstärkung danach linear auf 0 fällt, 0 schließt sofort
+    pub release_frames: u32,
+}
+
+impl GateSettings {
+    /// Schwelle `threshold_db` in dBFS, Halte- und Release-Zeit bei `sample_rate`.
+    pub fn new(threshold_db: f32, hold: Duration, release: Duration, sample_rate: u32) -> Self {
+        let frames = |duration: Duration| (duration.as_secs_f64() * sample_rate as f64).round() as u32;
+        GateSettings { threshold: 10f32.powf(threshold_db / 20.0), hold_frames: frames(hold), release_frames: frames(release) }
+    }
+}
+
+/// Zustand des Gates je Kanal.
+#[derive(Clone, Copy, Debug, Default)]
+struct ChannelState {
+    /// Verbleibende Frames der Haltezeit
+    hold: u32,
+    /// Aktuelle Verstärkung, 1 offen, 0 geschlossen
+    gain: f32,
+}
+
+#[derive(Default)]
+pub struct NoiseGate {
+    state: Vec<ChannelState>,
+}
+
+impl NoiseGate {
+    pub const fn new() -> Self {
+        NoiseGate { state: Vec::new() }
+    }
+
+    /// Wendet das Gate an Ort und Stelle auf interleavte Samples an, `samples[0]` gehört zu
+    /// `first_channel`. Ändert sich die Kanalzahl, beginnen alle Kanäle geschlossen.
+    pub fn process(&mut self, samples: &mut [f32], channels: usize, first_channel: usize, settings: GateSettings) {
+        let channels = channels.max(1);
+        if self.state.len() != channels {
+            self.state.clear();
+            self.state.resize(channels, ChannelState::default());
+        }
+        let release_step = if settings.release_frames > 0 { 1.0 / settings.release_frames as f32 } else { 1.0 };
+        for (idx, sample) in samples.iter_mut().enumerate() {
+            let channel = &mut self.state[(first_channel + idx) % channels];
+            if sample.abs() >= settings.threshold {
+                channel.hold = settings.hold_frames;
+                channel.gain = 1.0;
+            } else if channel.hold > 0 {
+                channel.hold -= 1;
+            } else {
+                channel.gain = (channel.gain - release_step).max(0.0);
+            }
+            *sample *= channel.gain;
+        }
+    }
+
+    /// Ob das Gate in mindestens einem Kanal offen ist.
+    pub fn is_open(&self) -> bool {
+        self.state.iter().any(|channel| channel.gain > 0.0)
+    }
+
+    /// Schließt das Gate in allen Kanälen.
+    pub fn reset(&mut self) {
+        self.state.fill(ChannelState::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_gate_holds_then_releases() {
+        let settings = GateSettings { threshold: 0.1, hold_frames: 2, release_frames: 4 };
+        let input = [0.5, 0.01, 0.01, 0.01, 0.01, 0.01, 0.01, 0.01, 0.5];
+        let expected = [0.5, 0.01, 0.01, 0.0075, 0.005, 0.0025, 0.0, 0.0, 0.5];
+        // Auf zwei Aufrufe verteilt, der Zustand läuft über die Puffergrenze weiter
+        let mut gate = NoiseGate::new();
+        let mut samples = input;
+        let (first, second) = samples.split_at_mut(4);
+        gate.process(first, 1, 0, settings);
+        gate.process(second, 1, 0, settings);
+        assert!(samples.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-6), "{:?}", samples);
+        assert!(gate.is_open());
+        gate.reset();
+        assert!(!gate.is_open());
+
+        let settings = GateSettings::new(-20.0, Duration::from_millis(10), Duration::from_millis(5), 48000);
+        assert!((settings.threshold - 0.1).abs() < 1e-6);
+        assert_eq!((settings.hold_frames, settings.release_frames), (480, 240));
+    }
+}
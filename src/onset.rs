@@ -0,0 +1,41 @@
+// Einsatzerkennung (Onsets) über die Ableitung des Pegels: steigt der Pegel von einem Chunk zum
+// nächsten um mindestens die Schwelle, beginnt ein neues Ereignis. Der Mindestabstand verhindert,
+// dass ein Anschlag über mehrere Chunks mehrfach erkannt wird.
+
+/// Pegel, unterhalb dessen kein Einsatz erkannt wird, damit Rauschen keine Marker erzeugt
+const FLOOR_DB: f32 = -60.0;
+
+/// Liefert die Indizes der Punkte in `levels` (linear, ±1.0 bei Vollausschlag), an denen der
+/// Pegel gegenüber dem vorherigen Punkt um mindestens `threshold_db` steigt. Zwischen zwei
+/// Einsätzen liegen mindestens `min_interval` Punkte.
+pub fn detect_onsets(levels: impl IntoIterator<Item = f32>, threshold_db: f32, min_interval: usize) -> Vec<usize> {
+    let mut onsets = Vec::new();
+    let mut previous_db = FLOOR_DB;
+    let mut last_onset: Option<usize> = None;
+    for (idx, level) in levels.into_iter().enumerate() {
+        let level_db = (20.0 * level.abs().log10()).max(FLOOR_DB);
+        let rested = last_onset.is_none_or(|last| idx - last >= min_interval);
+        if level_db > FLOOR_DB && level_db - previous_db >= threshold_db && rested {
+            onsets.push(idx);
+            last_onset = Some(idx);
+        }
+        previous_db = level_db;
+    }
+    onsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn onsets_respect_threshold_and_interval() {
+        // Zwei Anschläge mit Abklingen, der zweite steigt über zwei Chunks an
+        let levels = [0.0, 0.0, 0.5, 0.3, 0.2, 0.1, 0.05, 0.2, 0.8, 0.4, 0.0005, 0.001];
+        assert_eq!(detect_onsets(levels, 6.0, 1), vec![2, 7, 8]);
+        // Der Mindestabstand unterdrückt den zweiten Teil des Anstiegs
+        assert_eq!(detect_onsets(levels, 6.0, 3), vec![2, 7]);
+        // Anstiege im Rauschen unter -60 dBFS zählen nicht
+        assert_eq!(detect_onsets([0.0005, 0.0009], 3.0, 1), Vec::<usize>::new());
+    }
+}
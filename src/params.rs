@@ -0,0 +1,199 @@
+// Verarbeitungsparameter, die die UI während des Streams ändert und der Audio-Callback liest.
+// Jedes Feld ist ein eigenes Atomic, damit weder UI noch Audio-Thread sperren müssen.
+//
+// Speicherordnung: alle Zugriffe sind `Relaxed`. Jeder Parameter ist für sich ein gültiger Wert
+// und schützt keine anderen Daten, eine Happens-before-Beziehung wird also nicht gebraucht. Der
+// Callback liest jeden Parameter einmal je Puffer und sieht eine Änderung spätestens beim
+// nächsten Puffer. Die Felder sind untereinander nicht synchronisiert: ändern sich zwei
+// gleichzeitig, kann ein Puffer den neuen Wert des einen und den alten des anderen sehen.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, AtomicUsize, Ordering};
+
+use crate::WaveformMode;
+
+pub struct Params {
+    /// Interleavte Samples je Punkt
+    chunk_size: AtomicUsize,
+    /// `WaveformMode` als u8
+    waveform_mode: AtomicU8,
+    /// Alle Kanäle vor der Reduktion zu einer Mono-Summe mitteln
+    downmix: AtomicBool,
+    /// Grenzfrequenz des DC-Filters in Hz, 0 = aus
+    dc_cutoff_hz: AtomicU32,
+    /// Samples vor der Reduktion A-bewerten
+    a_weighting: AtomicBool,
+    /// Schwelle des Noise-Gates in dBFS als Bits eines f32, NaN = aus
+    gate_threshold_db: AtomicU32,
+    /// Haltezeit des Noise-Gates in ms
+    gate_hold_ms: AtomicU32,
+    /// Release-Zeit des Noise-Gates in ms
+    gate_release_ms: AtomicU32,
+    /// Kanäle mit gedrehter Phase als Bitmaske, Bit n steht für Kanal n
+    inverted_channels: AtomicU32,
+    /// Samples vor der Reduktion durch den Hüllkurvenfolger schicken
+    envelope: AtomicBool,
+    /// Attack-Zeit des Hüllkurvenfolgers in ms
+    envelope_attack_ms: AtomicU32,
+    /// Release-Zeit des Hüllkurvenfolgers in ms
+    envelope_release_ms: AtomicU32,
+    /// Beim nächsten Puffer Rest und Filterzustand verwerfen, z. B. nach einer Pause
+    discard_remainder: AtomicBool,
+    /// Mono-Samples für Spektrogramm und Oszilloskop liefern
+    mono_samples_enabled: AtomicBool,
+}
+
+impl Params {
+    pub fn new(chunk_size: usize) -> Self {
+        Params {
+            chunk_size: AtomicUsize::new(chunk_size.max(1)),
+            waveform_mode: AtomicU8::new(WaveformMode::default() as u8),
+            downmix: AtomicBool::new(false),
+            dc_cutoff_hz: AtomicU32::new(0),
+            a_weighting: AtomicBool::new(false),
+            gate_threshold_db: AtomicU32::new(f32::NAN.to_bits()),
+            gate_hold_ms: AtomicU32::new(0),
+            gate_release_ms: AtomicU32::new(0),
+            inverted_channels: AtomicU32::new(0),
+            envelope: AtomicBool::new(false),
+            envelope_attack_ms: AtomicU32::new(0),
+            envelope_release_ms: AtomicU32::new(0),
+            discard_remainder: AtomicBool::new(false),
+            mono_samples_enabled: AtomicBool::new(false),
+        }
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size.load(Ordering::Relaxed)
+    }
+
+    pub fn set_chunk_size(&self, chunk_size: usize) {
+        self.chunk_size.store(chunk_size.max(1), Ordering::Relaxed);
+    }
+
+    pub fn waveform_mode(&self) -> WaveformMode {
+        WaveformMode::from_u8(self.waveform_mode.load(Ordering::Relaxed))
+    }
+
+    pub fn set_waveform_mode(&self, mode: WaveformMode) {
+        self.waveform_mode.store(mode as u8, Ordering::Relaxed);
+    }
+
+    pub fn downmix(&self) -> bool {
+        self.downmix.load(Ordering::Relaxed)
+    }
+
+    pub fn set_downmix(&self, enabled: bool) {
+        self.downmix.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Grenzfrequenz des DC-Filters, `None` wenn es aus ist.
+    pub fn dc_cutoff_hz(&self) -> Option<u32> {
+        match self.dc_cutoff_hz.load(Ordering::Relaxed) {
+            0 => None,
+            cutoff_hz => Some(cutoff_hz),
+        }
+    }
+
+    pub fn set_dc_cutoff_hz(&self, cutoff_hz: Option<u32>) {
+        self.dc_cutoff_hz.store(cutoff_hz.map_or(0, |cutoff_hz| cutoff_hz.max(1)), Ordering::Relaxed);
+    }
+
+    pub fn a_weighting(&self) -> bool {
+        self.a_weighting.load(Ordering::Relaxed)
+    }
+
+    pub fn set_a_weighting(&self, enabled: bool) {
+        self.a_weighting.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Schwelle des Noise-Gates in dBFS, `None` wenn es aus ist.
+    pub fn gate_threshold_db(&self) -> Option<f32> {
+        let threshold_db = f32::from_bits(self.gate_threshold_db.load(Ordering::Relaxed));
+        (!threshold_db.is_nan()).then_some(threshold_db)
+    }
+
+    pub fn set_gate_threshold_db(&self, threshold_db: Option<f32>) {
+        self.gate_threshold_db.store(threshold_db.unwrap_or(f32::NAN).to_bits(), Ordering::Relaxed);
+    }
+
+    /// (Haltezeit, Release-Zeit) des Noise-Gates in ms
+    pub fn gate_timing_ms(&self) -> (u32, u32) {
+        (self.gate_hold_ms.load(Ordering::Relaxed), self.gate_release_ms.load(Ordering::Relaxed))
+    }
+
+    pub fn set_gate_timing_ms(&self, hold_ms: u32, release_ms: u32) {
+        self.gate_hold_ms.store(hold_ms, Ordering::Relaxed);
+        self.gate_release_ms.store(release_ms, Ordering::Relaxed);
+    }
+
+    pub fn inverted_channels(&self) -> u32 {
+        self.inverted_channels.load(Ordering::Relaxed)
+    }
+
+    pub fn set_inverted_channels(&self, inverted: u32) {
+        self.inverted_channels.store(inverted, Ordering::Relaxed);
+    }
+
+    pub fn envelope(&self) -> bool {
+        self.envelope.load(Ordering::Relaxed)
+    }
+
+    pub fn set_envelope(&self, enabled: bool) {
+        self.envelope.store(enabled, Ordering::Relaxed);
+    }
+
+    /// (Attack-Zeit, Release-Zeit) des Hüllkurvenfolgers in ms
+    pub fn envelope_timing_ms(&self) -> (u32, u32) {
+        (self.envelope_attack_ms.load(Ordering::Relaxed), self.envelope_release_ms.load(Ordering::Relaxed))
+    }
+
+    pub fn set_envelope_timing_ms(&self, attack_ms: u32, release_ms: u32) {
+        self.envelope_attack_ms.store(attack_ms, Ordering::Relaxed);
+        self.envelope_release_ms.store(release_ms, Ordering::Relaxed);
+    }
+
+    /// Liefert `true`, wenn seit dem letzten Aufruf `discard_remainder` angefordert wurde.
+    pub fn take_discard_remainder(&self) -> bool {
+        self.discard_remainder.swap(false, Ordering::Relaxed)
+    }
+
+    pub fn discard_remainder(&self) {
+        self.discard_remainder.store(true, Ordering::Relaxed);
+    }
+
+    pub fn mono_samples_enabled(&self) -> bool {
+        self.mono_samples_enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_mono_samples_enabled(&self, enabled: bool) {
+        self.mono_samples_enabled.store(enabled, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn params_are_shared_with_the_audio_thread() {
+        let params = Arc::new(Params::new(0));
+        assert_eq!(params.chunk_size(), 1);
+        assert_eq!(params.dc_cutoff_hz(), None);
+
+        let audio_side = params.clone();
+        std::thread::spawn(move || {
+            audio_side.set_waveform_mode(WaveformMode::Rms);
+            audio_side.set_dc_cutoff_hz(Some(0));
+            audio_side.discard_remainder();
+        })
+        .join()
+        .unwrap();
+        assert_eq!(params.waveform_mode(), WaveformMode::Rms);
+        assert_eq!(params.dc_cutoff_hz(), Some(1));
+        // Die Anforderung wird genau einmal abgeholt
+        assert!(params.take_discard_remainder());
+        assert!(!params.take_discard_remainder());
+    }
+}
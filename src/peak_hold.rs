@@ -0,0 +1,47 @@
+// Spitzenwert-Anzeige: je Pixelspalte der größte bisher gezeigte Wert, der mit der Zeit abfällt.
+
+use std::time::Duration;
+
+use crate::render_plot::TracePoint;
+
+pub struct PeakHold {
+    /// Gehaltener Wert je Spalte für (obere, untere) Spur
+    peaks: Vec<TracePoint>,
+}
+
+impl PeakHold {
+    pub fn new(width: usize) -> Self {
+        PeakHold { peaks: vec![(0.0, 0.0); width] }
+    }
+
+    /// Lässt die gehaltenen Werte um `decay_db_per_second` über die Dauer `elapsed` abfallen und
+    /// übernimmt dann größere Werte aus `traces`. Punkt `idx` gehört wie in `render_plot` zu
+    /// Spalte `idx - start`. Über die Zeit statt über Frames, damit der Abfall nicht von der
+    /// Bildrate abhängt.
+    pub fn update(&mut self, traces: &[TracePoint], start: i32, elapsed: Duration, decay_db_per_second: f32) {
+        let factor = 10f32.powf(-decay_db_per_second.max(0.0) * elapsed.as_secs_f32() / 20.0);
+        for (left, right) in self.peaks.iter_mut() {
+            *left *= factor;
+            *right *= factor;
+        }
+
+        for (idx, &(left, right)) in traces.iter().enumerate() {
+            let x = idx as i64 - start as i64;
+            if x < 0 || x >= self.peaks.len() as i64 {
+                continue;
+            }
+            let peak = &mut self.peaks[x as usize];
+            peak.0 = peak.0.max(left);
+            peak.1 = peak.1.max(right);
+        }
+    }
+
+    /// Verwirft alle gehaltenen Werte.
+    pub fn reset(&mut self) {
+        self.peaks.fill((0.0, 0.0));
+    }
+
+    pub fn peaks(&self) -> &[TracePoint] {
+        &self.peaks
+    }
+}
@@ -0,0 +1,103 @@
+// Queue der Punkte vom Audio-Thread zur UI. Kommt die UI nicht hinterher, verdrängt jeder neue
+// Punkt den ältesten wartenden, damit die Anzeige nach einem Hänger mit den neuesten Punkten
+// weitermacht. Dafür teilen sich beide Seiten den Consumer; der Audio-Thread greift nur mit
+// `try_lock` zu und wartet nie auf die UI.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use rmnc::{Meters, WaveformPoint};
+use rtrb::{Consumer, Producer, RingBuffer};
+
+/// Legt eine Queue für `capacity` Punkte an, verworfene Punkte werden in `meters` gezählt.
+pub fn point_queue(capacity: usize, meters: Arc<Meters>) -> (PointSender, PointReceiver) {
+    let (producer, consumer) = RingBuffer::new(capacity);
+    let consumer = Arc::new(Mutex::new(consumer));
+    (PointSender { producer, consumer: consumer.clone(), meters }, PointReceiver { consumer })
+}
+
+/// Seite des Audio-Threads.
+pub struct PointSender {
+    producer: Producer<WaveformPoint>,
+    consumer: Arc<Mutex<Consumer<WaveformPoint>>>,
+    meters: Arc<Meters>,
+}
+
+impl PointSender {
+    /// Hängt `point` an, ohne zu blockieren. Bei voller Queue wird der älteste Punkt verworfen.
+    /// Liest die UI in diesem Moment, geht stattdessen `point` verloren; gezählt wird beides in
+    /// `Meters::dropped_points`.
+    pub fn push(&mut self, point: WaveformPoint) {
+        if self.producer.is_full() {
+            if let Ok(mut consumer) = self.consumer.try_lock() {
+                if consumer.pop().is_ok() {
+                    self.meters.drop_points(1);
+                }
+            }
+        }
+        if self.producer.push(point).is_err() {
+            self.meters.drop_points(1);
+        }
+    }
+}
+
+/// Seite der UI.
+pub struct PointReceiver {
+    consumer: Arc<Mutex<Consumer<WaveformPoint>>>,
+}
+
+impl PointReceiver {
+    /// Zugriff auf die wartenden Punkte. Solange er besteht, kann der Audio-Thread keine alten
+    /// Punkte verdrängen.
+    pub fn lock(&self) -> MutexGuard<'_, Consumer<WaveformPoint>> {
+        // Die Queue bleibt auch nach einer Panik in der anderen Seite gültig
+        self.consumer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Verwirft alle wartenden Punkte, z. B. nach "Clear" oder einer Pause.
+    pub fn clear(&self) {
+        let mut consumer = self.lock();
+        let slots = consumer.slots();
+        if let Ok(chunk) = consumer.read_chunk(slots) {
+            chunk.commit_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmnc::MAX_CHANNELS;
+
+    fn point(level: f32) -> WaveformPoint {
+        WaveformPoint::from_levels([level; MAX_CHANNELS])
+    }
+
+    fn levels(receiver: &PointReceiver) -> Vec<f32> {
+        let mut consumer = receiver.lock();
+        std::iter::from_fn(|| consumer.pop().ok()).map(|point| point.level(0)).collect()
+    }
+
+    #[test]
+    fn full_queue_drops_the_oldest_points() {
+        let meters = Arc::new(Meters::new());
+        let (mut sender, receiver) = point_queue(4, meters.clone());
+        for idx in 0..7 {
+            sender.push(point(idx as f32));
+        }
+        assert_eq!(levels(&receiver), [3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(meters.dropped_points(), 3);
+    }
+
+    #[test]
+    fn reading_ui_drops_the_new_point_instead() {
+        let meters = Arc::new(Meters::new());
+        let (mut sender, receiver) = point_queue(2, meters.clone());
+        sender.push(point(0.0));
+        sender.push(point(1.0));
+        let guard = receiver.lock();
+        sender.push(point(2.0));
+        drop(guard);
+        assert_eq!(levels(&receiver), [0.0, 1.0]);
+        assert_eq!(meters.dropped_points(), 1);
+    }
+}
@@ -0,0 +1,326 @@
+// Aufnahme der unveränderten Eingangs-Samples in eine WAV-Datei. Der Audio-Thread schiebt die
+// Samples nur in eine Queue, geschrieben wird in einem eigenen Thread.
+//
+// Für den unbeaufsichtigten Betrieb kann der Recorder außerdem scharf geschaltet werden. Dann
+// hält der Schreib-Thread die letzten Sekunden in einem Vorlauf, und ein ausgelöster Clip
+// beginnt mit diesem Vorlauf und endet nach einer festen Länge von selbst.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use hound::{WavSpec, WavWriter};
+use rtrb::{Consumer, Producer, RingBuffer};
+
+use crate::capture::Meters;
+
+/// Kapazität der Queue in Samples, reicht bei 48 kHz Stereo für gut eine Sekunde
+const QUEUE_CAPACITY: usize = 1 << 17;
+
+/// Abstand, in dem der Schreib-Thread die Queue leert
+const WRITE_INTERVAL: Duration = Duration::from_millis(20);
+
+enum Command {
+    Start(WavWriter<BufWriter<File>>),
+    /// Clip mit dem Vorlauf und danach der angegebenen Zahl Samples
+    Clip(WavWriter<BufWriter<File>>, usize),
+    /// Länge des Vorlaufs in Samples, 0 schaltet ihn ab
+    PreRoll(usize),
+    Stop,
+    Shutdown,
+}
+
+/// Steuert den Schreib-Thread eines Streams. Beim Drop wird eine laufende Aufnahme abgeschlossen.
+pub struct Recorder {
+    spec: WavSpec,
+    commands: Sender<Command>,
+    /// Solange gesetzt, gibt der Audio-Thread seine Samples an die Queue
+    recording: Arc<AtomicBool>,
+    /// Eine mit `start` begonnene Aufnahme läuft
+    manual: AtomicBool,
+    /// Clips können ausgelöst werden, der Vorlauf wird gefüllt
+    armed: AtomicBool,
+    /// Ein ausgelöster Clip wird noch geschrieben, vom Schreib-Thread zurückgesetzt
+    clip_running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Seite des Audio-Threads: gibt Samples weiter, solange eine Aufnahme läuft.
+pub struct RecordSink<T> {
+    producer: Producer<T>,
+    recording: Arc<AtomicBool>,
+    channels: usize,
+    meters: Arc<Meters>,
+}
+
+impl<T: Copy> RecordSink<T> {
+    /// Übernimmt die Samples ohne zu blockieren. In die Queue kommen nur ganze Frames, sonst
+    /// landete jedes spätere Sample im falschen Kanal. Was bei voller Queue keinen Platz mehr
+    /// findet, geht verloren und wird in `Meters::dropped_record_frames` gezählt.
+    pub fn push(&mut self, data: &[T]) {
+        self.push_converted(data.iter().copied());
+    }
+
+    /// Wie `push`, für Samples, die erst in das Aufnahmeformat umgerechnet werden.
+    pub fn push_converted(&mut self, samples: impl ExactSizeIterator<Item = T>) {
+        if self.recording.load(Ordering::Relaxed) {
+            let len = samples.len();
+            let count = len.min(self.producer.slots()) / self.channels * self.channels;
+            if count < len {
+                self.meters.drop_record_frames((len - count).div_ceil(self.channels));
+            }
+            if let Ok(chunk) = self.producer.write_chunk_uninit(count) {
+                chunk.fill_from_iter(samples);
+            }
+        }
+    }
+}
+
+impl Recorder {
+    /// Startet den Schreib-Thread für Samples vom Typ `T` im Format `spec`. Verlorene Frames
+    /// zählt die Seite des Audio-Threads in `meters`.
+    pub fn spawn<T: hound::Sample + Copy + Send + 'static>(spec: WavSpec, meters: Arc<Meters>) -> (Recorder, RecordSink<T>) {
+        let (producer, consumer) = RingBuffer::<T>::new(QUEUE_CAPACITY);
+        let (commands, receiver) = mpsc::channel();
+        let recording = Arc::new(AtomicBool::new(false));
+        let clip_running = Arc::new(AtomicBool::new(false));
+        let output = Output { writer: None, clip_remaining: None, pre_roll: VecDeque::new(), pre_roll_len: 0, clip_running: clip_running.clone() };
+        let thread = std::thread::spawn(move || write_loop(consumer, receiver, output));
+        let sink = RecordSink { producer, recording: recording.clone(), channels: spec.channels.max(1) as usize, meters };
+        let recorder = Recorder { spec, commands, recording, manual: AtomicBool::new(false), armed: AtomicBool::new(false), clip_running, thread: Some(thread) };
+        (recorder, sink)
+    }
+
+    /// Beginnt eine Aufnahme nach `path`. Fehler beim Anlegen der Datei werden direkt geliefert,
+    /// eine laufende Aufnahme wird vorher abgeschlossen.
+    pub fn start(&self, path: &Path) -> Result<(), hound::Error> {
+        let writer = WavWriter::create(path, self.spec)?;
+        self.send(Command::Start(writer))?;
+        self.manual.store(true, Ordering::Relaxed);
+        self.recording.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Beendet die Aufnahme, der Schreib-Thread schreibt noch die gepufferten Samples.
+    pub fn stop(&self) {
+        self.manual.store(false, Ordering::Relaxed);
+        self.recording.store(self.armed.load(Ordering::Relaxed), Ordering::Relaxed);
+        let _ = self.commands.send(Command::Stop);
+    }
+
+    /// Schaltet ausgelöste Clips mit einem Vorlauf von `pre_roll` scharf oder mit `None` ab. Der
+    /// Vorlauf beginnt leer.
+    pub fn arm(&self, pre_roll: Option<Duration>) {
+        let _ = self.commands.send(Command::PreRoll(pre_roll.map_or(0, |duration| self.samples(duration))));
+        self.armed.store(pre_roll.is_some(), Ordering::Relaxed);
+        self.recording.store(pre_roll.is_some() || self.manual.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    /// Schreibt den Vorlauf und die folgenden `length` nach `path`. Der Clip ersetzt eine laufende
+    /// Aufnahme und endet von selbst, siehe `clip_running`.
+    pub fn save_clip(&self, path: &Path, length: Duration) -> Result<(), hound::Error> {
+        let writer = WavWriter::create(path, self.spec)?;
+        // Vor dem Senden, sonst könnte der fertige Clip das Flag zurücksetzen, bevor es gesetzt ist
+        self.clip_running.store(true, Ordering::Relaxed);
+        if let Err(err) = self.send(Command::Clip(writer, self.samples(length))) {
+            self.clip_running.store(false, Ordering::Relaxed);
+            return Err(err);
+        }
+        self.manual.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Ob ein mit `save_clip` ausgelöster Clip noch geschrieben wird.
+    pub fn clip_running(&self) -> bool {
+        self.clip_running.load(Ordering::Relaxed)
+    }
+
+    /// Anzahl interleavter Samples für `duration`, immer ganze Frames.
+    fn samples(&self, duration: Duration) -> usize {
+        let frames = (duration.as_secs_f64() * self.spec.sample_rate as f64).round() as usize;
+        frames * self.spec.channels as usize
+    }
+
+    fn send(&self, command: Command) -> Result<(), hound::Error> {
+        self.commands.send(command).map_err(|_| hound::Error::IoError(std::io::Error::other("writer thread stopped")))
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.recording.store(false, Ordering::Relaxed);
+        let _ = self.commands.send(Command::Shutdown);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Ziel der Samples im Schreib-Thread.
+struct Output<T> {
+    writer: Option<WavWriter<BufWriter<File>>>,
+    /// Samples, die dem laufenden Clip noch fehlen, `None` bei einer Aufnahme über `start`
+    clip_remaining: Option<usize>,
+    /// Die letzten Samples, solange keine Datei offen ist
+    pre_roll: VecDeque<T>,
+    pre_roll_len: usize,
+    clip_running: Arc<AtomicBool>,
+}
+
+impl<T: hound::Sample + Copy> Output<T> {
+    /// Schreibt die Samples in die offene Datei oder, ohne Datei, in den Vorlauf. Ist ein Clip
+    /// vollständig, wird er abgeschlossen, und die übrigen Samples gehen in den Vorlauf.
+    fn write(&mut self, samples: impl IntoIterator<Item = T>) {
+        for sample in samples {
+            let Some(wav) = self.writer.as_mut() else {
+                if self.pre_roll_len > 0 {
+                    if self.pre_roll.len() >= self.pre_roll_len {
+                        self.pre_roll.pop_front();
+                    }
+                    self.pre_roll.push_back(sample);
+                }
+                continue;
+            };
+            if let Err(err) = wav.write_sample(sample) {
+                eprintln!("Error writing recording: {}", err);
+                self.writer = None;
+                self.close();
+                continue;
+            }
+            if let Some(remaining) = self.clip_remaining.as_mut() {
+                *remaining = remaining.saturating_sub(1);
+                if *remaining == 0 {
+                    self.close();
+                }
+            }
+        }
+    }
+
+    /// Beginnt einen Clip mit dem bisherigen Vorlauf, danach fehlen ihm noch `length` Samples.
+    fn start_clip(&mut self, writer: WavWriter<BufWriter<File>>, length: usize) {
+        self.close();
+        // `close` meldet den vorherigen Clip als fertig, der neue läuft aber schon
+        self.clip_running.store(true, Ordering::Relaxed);
+        self.writer = Some(writer);
+        let mut pre_roll = std::mem::take(&mut self.pre_roll);
+        self.write(pre_roll.drain(..));
+        // Die Kapazität bleibt für den nächsten Vorlauf erhalten
+        self.pre_roll = pre_roll;
+        if length == 0 {
+            self.close();
+        } else {
+            self.clip_remaining = Some(length);
+        }
+    }
+
+    fn set_pre_roll(&mut self, len: usize) {
+        self.pre_roll.clear();
+        self.pre_roll_len = len;
+    }
+
+    /// Schließt die offene Datei ab, auch einen unvollständigen Clip.
+    fn close(&mut self) {
+        finalize(self.writer.take());
+        self.clip_remaining = None;
+        self.clip_running.store(false, Ordering::Relaxed);
+    }
+}
+
+fn write_loop<T: hound::Sample + Copy>(mut consumer: Consumer<T>, commands: mpsc::Receiver<Command>, mut output: Output<T>) {
+    loop {
+        let command = commands.recv_timeout(WRITE_INTERVAL);
+        // Erst die Queue leeren, damit beim Stoppen nichts verloren geht und ein Clip den
+        // Vorlauf bis zum Auslösen enthält
+        drain(&mut consumer, &mut output);
+        match command {
+            Ok(Command::Start(new_writer)) => {
+                output.close();
+                output.writer = Some(new_writer);
+            }
+            Ok(Command::Clip(new_writer, length)) => output.start_clip(new_writer, length),
+            Ok(Command::PreRoll(len)) => output.set_pre_roll(len),
+            Ok(Command::Stop) => output.close(),
+            Ok(Command::Shutdown) | Err(RecvTimeoutError::Disconnected) => {
+                output.close();
+                return;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+    }
+}
+
+/// Übergibt alle Samples aus der Queue an `output`.
+fn drain<T: hound::Sample + Copy>(consumer: &mut Consumer<T>, output: &mut Output<T>) {
+    if let Ok(chunk) = consumer.read_chunk(consumer.slots()) {
+        let (first, second) = chunk.as_slices();
+        output.write(first.iter().chain(second).copied());
+        chunk.commit_all();
+    }
+}
+
+/// Schreibt den WAV-Header mit der endgültigen Länge.
+fn finalize(writer: Option<WavWriter<BufWriter<File>>>) {
+    if let Some(writer) = writer {
+        if let Err(err) = writer.finalize() {
+            eprintln!("Error finalizing recording: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_queue_keeps_whole_frames() {
+        // Stereo mit Platz für 5 Samples, nach zwei Frames bleibt ein einzelner Platz frei
+        let (producer, mut consumer) = RingBuffer::<i16>::new(5);
+        let meters = Arc::new(Meters::new());
+        let mut sink = RecordSink { producer, recording: Arc::new(AtomicBool::new(true)), channels: 2, meters: meters.clone() };
+        sink.push(&[1, 2, 3, 4]);
+        sink.push(&[5, 6]);
+        sink.push_converted([7, 8, 9, 10].into_iter());
+        assert_eq!(consumer.slots(), 4);
+        assert_eq!(meters.dropped_record_frames(), 3);
+
+        // Nach dem Leeren stimmt die Zuordnung der Kanäle weiterhin
+        let drained: Vec<i16> = std::iter::from_fn(|| consumer.pop().ok()).collect();
+        assert_eq!(drained, [1, 2, 3, 4]);
+        sink.push(&[11, 12, 13, 14, 15, 16]);
+        let drained: Vec<i16> = std::iter::from_fn(|| consumer.pop().ok()).collect();
+        assert_eq!(drained, [11, 12, 13, 14]);
+        assert_eq!(meters.dropped_record_frames(), 4);
+    }
+
+    #[test]
+    fn triggered_clip_starts_with_the_pre_roll() {
+        let spec = WavSpec { channels: 1, sample_rate: 1000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let path = std::env::temp_dir().join(format!("rmnc-clip-test-{}.wav", std::process::id()));
+        let (mut producer, mut consumer) = RingBuffer::<i16>::new(1024);
+        let clip_running = Arc::new(AtomicBool::new(false));
+        let mut output = Output { writer: None, clip_remaining: None, pre_roll: VecDeque::new(), pre_roll_len: 0, clip_running: clip_running.clone() };
+        output.set_pre_roll(50);
+        // Wie in `write_loop`: was vor dem Auslösen in der Queue liegt, gehört noch zum Vorlauf
+        for sample in 0..200 {
+            producer.push(sample).unwrap();
+        }
+        drain(&mut consumer, &mut output);
+        output.start_clip(WavWriter::create(&path, spec).unwrap(), 30);
+        assert!(clip_running.load(Ordering::Relaxed));
+        for sample in 200..300 {
+            producer.push(sample).unwrap();
+        }
+        drain(&mut consumer, &mut output);
+        assert!(!clip_running.load(Ordering::Relaxed));
+        let samples: Vec<i16> = hound::WavReader::open(&path).unwrap().samples().map(Result::unwrap).collect();
+        std::fs::remove_file(&path).unwrap();
+        // 50 Samples Vorlauf, danach 30 Samples
+        assert_eq!(samples, (150..230).collect::<Vec<i16>>());
+    }
+}
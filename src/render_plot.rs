@@ -0,0 +1,725 @@
+// Rendert den Verlauf als Rasterbild, als Alternative zum nativen Slint-Plot.
+// Das Layout entspricht dem nativen Plot: obere Hälfte linker, untere Hälfte rechter Kanal, oder
+// beide Spuren übereinander auf derselben Achse (siehe `PlotLayout`).
+// Ein `TracePoint` trägt die Pegel zweier Kanäle, kein (min, max)-Paar. Die Werte mit Vorzeichen
+// aus `WaveformPoint::min` und `max` kommen über eine `Envelope`: Mit ihr reicht jede Spalte vom
+// kleinsten bis zum größten Wert, ohne sie wird der Pegel symmetrisch um die Mittellinie seiner
+// Spur gezeichnet.
+
+use std::path::Path;
+
+use image::{ImageBuffer, ImageResult, Rgba};
+use slint::{Color, Image, Rgba8Pixel, SharedPixelBuffer};
+
+use crate::PlotStyle;
+
+/// Ein Punkt des Verlaufs: (Spur A, Spur B), je der Pegel (≥ 0) eines Kanals, wie ihn
+/// `WaveformPoint::level` aus dem kleinsten und größten Wert bildet
+pub type TracePoint = (f32, f32);
+
+/// Vorzeichenbehaftete Hüllkurve zu einem Verlauf: je Punkt die kleinsten und die größten Werte
+/// beider Spuren, jeweils als (Spur A, Spur B).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Envelope {
+    pub lows: Vec<TracePoint>,
+    pub highs: Vec<TracePoint>,
+}
+
+/// Wert, bei dem eine Spur ihre Hälfte des Plots ganz ausfüllt (wie im nativen Plot)
+pub const LANE_FULL_SCALE: f32 = 2.0;
+
+/// Vertikale Skalierung der Amplitude.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AmplitudeScale {
+    /// Linear, `LANE_FULL_SCALE` füllt die Spur
+    #[default]
+    Linear,
+    /// Logarithmisch in dBFS, 0 dBFS füllt die Spur, Werte unter `floor_db` liegen auf der Mittellinie
+    Db { floor_db: f32 },
+}
+
+impl AmplitudeScale {
+    /// Wie `lane_fraction`, aber mit dem Vorzeichen von `value` (-1..=1).
+    pub fn signed_lane_fraction(self, value: f32) -> f32 {
+        self.lane_fraction(value.abs()).copysign(value)
+    }
+
+    /// Anteil der halben Spurhöhe (0..=1), den der Wert einnimmt.
+    pub fn lane_fraction(self, value: f32) -> f32 {
+        match self {
+            AmplitudeScale::Linear => value.clamp(0.0, LANE_FULL_SCALE) / LANE_FULL_SCALE,
+            AmplitudeScale::Db { floor_db } => {
+                if value <= 0.0 || floor_db >= 0.0 {
+                    return 0.0;
+                }
+                let db = 20.0 * value.log10();
+                ((db - floor_db) / -floor_db).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+/// Füllung der Spalten einer Spur.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TraceFill {
+    /// Einfarbig
+    #[default]
+    Solid,
+    /// Von der Mittellinie nach außen heller werdend, am Rand der Spur volle Farbe
+    Gradient,
+}
+
+/// Anordnung der beiden Spuren.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlotLayout {
+    /// Spur A in der oberen, Spur B in der unteren Hälfte
+    #[default]
+    Stacked,
+    /// Beide Spuren über die ganze Höhe um dieselbe Mittellinie, Spur B halbtransparent darüber
+    Overlay,
+}
+
+impl PlotLayout {
+    /// Höhe einer Spur und die Mittellinien von Spur A und B bei `plot_height` Pixeln.
+    fn lanes(self, plot_height: f32) -> (f32, [f32; 2]) {
+        match self {
+            PlotLayout::Stacked => (plot_height / 2.0, [plot_height / 4.0, plot_height * 0.75]),
+            PlotLayout::Overlay => (plot_height, [plot_height / 2.0; 2]),
+        }
+    }
+}
+
+/// Deckkraft von Spur B über Spur A bei `PlotLayout::Overlay`
+const OVERLAY_ALPHA: f32 = 0.6;
+
+/// Deckkraft der Verlaufsfüllung an der Mittellinie
+const GRADIENT_MIN_ALPHA: f32 = 0.25;
+
+/// Deckkraft des Bereichs zwischen kleinstem und größtem Wert einer Spalte
+const RANGE_ALPHA: f32 = 0.45;
+
+/// Höhe der Zeitachse am unteren Bildrand in logischen Pixeln
+const TIME_AXIS_HEIGHT: u32 = 16;
+
+/// Zeitbasis für die Zeitachse. Ein Punkt fasst `chunk_size` interleavte Samples zusammen.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeAxis<'a> {
+    pub sample_rate: u32,
+    pub chunk_size: usize,
+    pub channels: usize,
+    /// Anzahl Punkte je Pixelspalte, 1.0 in der Live-Ansicht
+    pub points_per_column: f32,
+    /// Anzahl Punkte zwischen dem neuesten Punkt und dem rechten Rand, 0 s liegt dann außerhalb
+    pub pan_points: usize,
+    /// Alter jeder Spalte in Sekunden vor dem neuesten Punkt laut den Zeitstempeln der Punkte,
+    /// NaN für Spalten ohne Punkt. Dann sitzen die Markierungen auch nach ausgefallenen Puffern
+    /// richtig, `None` nimmt gleichmäßige Abstände an.
+    pub column_ages: Option<&'a [f32]>,
+}
+
+impl TimeAxis<'_> {
+    /// Dauer eines Punktes in Sekunden
+    pub fn seconds_per_point(&self) -> f32 {
+        self.chunk_size as f32 / self.channels.max(1) as f32 / self.sample_rate.max(1) as f32
+    }
+
+    /// Dauer einer Pixelspalte in Sekunden
+    pub fn seconds_per_column(&self) -> f32 {
+        self.seconds_per_point() * self.points_per_column
+    }
+}
+
+/// Optionen für `render_plot`.
+#[derive(Clone, Copy, Debug)]
+pub struct PlotOptions<'a> {
+    pub scale: AmplitudeScale,
+    pub layout: PlotLayout,
+    pub fill: TraceFill,
+    /// Gitterlinien bei diesen Pegeln in dBFS zusätzlich zur Nulllinie, `None` ohne Gitter
+    pub grid_levels_db: Option<&'a [f32]>,
+    /// Zeitachse am unteren Rand, `None` ohne Achse
+    pub time_axis: Option<TimeAxis<'a>>,
+    /// Gehaltene Spitzenwerte je Spalte ab Spalte 0, `None` ohne Spitzenwert-Anzeige
+    pub peak_hold: Option<&'a [TracePoint]>,
+    /// Kleinster Wert je Spalte ab Spalte 0 (siehe `column_minimum`). Dann wird bis dahin voll
+    /// und darüber bis zum größten Wert heller gezeichnet, `None` zeigt nur den größten Wert.
+    pub column_min: Option<&'a [TracePoint]>,
+    /// Hüllkurve je Spalte ab Spalte 0 (siehe `fit_envelope`), `None` zeichnet symmetrisch
+    pub envelope: Option<&'a Envelope>,
+    /// Spalten mit einem erkannten Einsatz, als senkrechte Linie über beide Spuren
+    pub onsets: &'a [u32],
+    /// Spalten, vor denen Samples fehlen (Aussetzer), als senkrechte Linie in der Farbe der
+    /// Spitzenwerte
+    pub gaps: &'a [u32],
+    /// Obere bzw. untere Spur nicht zeichnen, z. B. weil ihr Kanal stummgeschaltet ist
+    pub hidden: [bool; 2],
+    /// Stärke der Spuren in logischen Pixeln. Die Ränder jeder Spalte werden um die zusätzliche
+    /// Stärke nach oben und unten verbreitert, 1 zeichnet genau den Bereich der Werte.
+    pub line_width: u32,
+    /// Physische Pixel je logischem Pixel. Das Bild selbst hat bereits die physische Größe,
+    /// Linienstärken, Zeitachse und Schrift werden damit vergrößert.
+    pub scale_factor: f32,
+}
+
+impl Default for PlotOptions<'_> {
+    /// Zwei einfarbige Spuren übereinander, linear, ohne Gitter, Achse und Markierungen, in
+    /// einfacher Stärke ohne Skalierung
+    fn default() -> Self {
+        PlotOptions {
+            scale: AmplitudeScale::default(),
+            layout: PlotLayout::default(),
+            fill: TraceFill::default(),
+            grid_levels_db: None,
+            time_axis: None,
+            peak_hold: None,
+            column_min: None,
+            envelope: None,
+            onsets: &[],
+            gaps: &[],
+            hidden: [false; 2],
+            line_width: 1,
+            scale_factor: 1.0,
+        }
+    }
+}
+
+/// Rendert die Spuren in ein Bild der Größe `width` x `height`. Punkt `idx` landet in Spalte
+/// `idx - start`, entsprechend `wav1start` im nativen Plot.
+pub fn render_plot(traces: &[TracePoint], start: i32, width: u32, height: u32, style: &PlotStyle, options: &PlotOptions) -> Image {
+    let img = render_plot_buffer(traces, start, width, height, style, options);
+    let buffer = SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(img.as_raw(), width, height);
+    Image::from_rgba8_premultiplied(buffer)
+}
+
+/// Wie `render_plot`, liefert aber das Bild mit vormultipliziertem Alpha statt eines Slint-Images.
+pub fn render_plot_buffer(traces: &[TracePoint], start: i32, width: u32, height: u32, style: &PlotStyle, options: &PlotOptions) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    let background = premultiply(style.background);
+    let trace_a = premultiply(style.trace_a);
+    let trace_b = match options.layout {
+        PlotLayout::Stacked => premultiply(style.trace_b),
+        PlotLayout::Overlay => faded(premultiply(style.trace_b), OVERLAY_ALPHA),
+    };
+    let mut img = ImageBuffer::from_pixel(width, height, background);
+    let scale = options.scale;
+    let line = scaled(1, options.scale_factor);
+    let stroke = scaled(options.line_width.max(1), options.scale_factor);
+
+    // Die Zeitachse bekommt einen eigenen Streifen, die Spuren teilen sich den Rest
+    let plot_height = match options.time_axis {
+        Some(_) => height.saturating_sub(scaled(TIME_AXIS_HEIGHT, options.scale_factor)),
+        None => height,
+    };
+    let (lane_height, centers) = options.layout.lanes(plot_height as f32);
+    // Das Gitter zuerst zeichnen, damit die Spuren darüber liegen
+    if let Some(levels) = options.grid_levels_db {
+        let grid = premultiply(style.grid);
+        // Übereinander liegende Spuren teilen sich ein Gitter
+        let grid_centers = if centers[0] == centers[1] { &centers[..1] } else { &centers[..] };
+        for &center_y in grid_centers {
+            draw_row(&mut img, center_y, line, grid);
+            for &level_db in levels {
+                let offset = scale.lane_fraction(10f32.powf(level_db / 20.0)) * lane_height / 2.0;
+                draw_row(&mut img, center_y - offset, line, grid);
+                draw_row(&mut img, center_y + offset, line, grid);
+            }
+        }
+    }
+
+    for (idx, &(left, right)) in traces.iter().enumerate() {
+        let x = idx as i64 - start as i64;
+        if x < 0 || x >= width as i64 {
+            continue;
+        }
+        let minimum = options.column_min.and_then(|column_min| column_min.get(x as usize));
+        let envelope = options.envelope.and_then(|envelope| Some((*envelope.lows.get(x as usize)?, *envelope.highs.get(x as usize)?)));
+        for (lane, (center_y, value, color)) in [(centers[0], left, trace_a), (centers[1], right, trace_b)].into_iter().enumerate() {
+            if options.hidden[lane] {
+                continue;
+            }
+            let fraction = scale.lane_fraction(value);
+            if fraction <= 0.0 {
+                // Empfangene Stille als Grundlinie, damit sie sich von Spalten ohne Punkt unterscheidet
+                let top = (center_y - (stroke - line) as f32 / 2.0).max(0.0) as u32;
+                draw_vertical(&mut img, x as u32, top, top + stroke, 1, color);
+                continue;
+            }
+            let pick = |(a, b): TracePoint| if lane == 0 { a } else { b };
+            let (low, high) = match envelope {
+                Some((lows, highs)) => (scale.signed_lane_fraction(pick(lows)), scale.signed_lane_fraction(pick(highs))),
+                None => (-fraction, fraction),
+            };
+            match minimum {
+                Some(&min) => {
+                    // Der volle Bereich bis zum kleinsten Betrag bleibt innerhalb der Hüllkurve
+                    let min_fraction = scale.lane_fraction(pick(min));
+                    draw_span(&mut img, x as u32, center_y, low, high, lane_height, stroke, faded(color, RANGE_ALPHA), options.fill);
+                    draw_span(&mut img, x as u32, center_y, low.max(-min_fraction), high.min(min_fraction), lane_height, stroke, color, options.fill);
+                }
+                None => draw_span(&mut img, x as u32, center_y, low, high, lane_height, stroke, color, options.fill),
+            }
+        }
+    }
+
+    let axis = premultiply(style.axis);
+    for &x in options.onsets {
+        draw_vertical(&mut img, x, 0, plot_height, line, axis);
+    }
+    let gap = premultiply(style.peak);
+    for &x in options.gaps {
+        draw_vertical(&mut img, x, 0, plot_height, line, gap);
+    }
+
+    // Spitzenwerte als Markierung über den Spuren
+    if let Some(peaks) = options.peak_hold {
+        let peak = premultiply(style.peak);
+        for (x, &(left, right)) in peaks.iter().enumerate().take(width as usize) {
+            if !options.hidden[0] {
+                draw_peak_marker(&mut img, x as u32, centers[0], scale.lane_fraction(left), lane_height, line, peak);
+            }
+            if !options.hidden[1] {
+                draw_peak_marker(&mut img, x as u32, centers[1], scale.lane_fraction(right), lane_height, line, peak);
+            }
+        }
+    }
+
+    if let Some(axis) = options.time_axis {
+        // Der neueste Punkt markiert 0 s, beim Verschieben liegt er rechts außerhalb
+        let newest_x = traces.len() as i64 - 1 - start as i64 + (axis.pan_points as f32 / axis.points_per_column.max(f32::EPSILON)).round() as i64;
+        draw_time_axis(&mut img, plot_height, newest_x, axis.seconds_per_column(), axis.column_ages, options.scale_factor, premultiply(style.axis));
+    }
+
+    img
+}
+
+/// Rendert einen Oszilloskop-Ausschnitt: `samples` (±1.0) über die ganze Breite, 0 in der Mitte.
+/// Je Spalte wird der Bereich der Samples gezeichnet, verbunden mit dem letzten Sample der
+/// vorherigen Spalte. `trigger_level` erscheint als waagrechte Linie. Breite und Höhe sind
+/// physische Pixel, `scale_factor` bestimmt die Linienstärke wie in `PlotOptions`. Bei
+/// `line_width` über 1 wird jede Spalte in beide Richtungen um die zusätzliche Stärke verbreitert.
+pub fn render_scope(samples: &[f32], width: u32, height: u32, style: &PlotStyle, trigger_level: Option<f32>, line_width: u32, scale_factor: f32) -> Image {
+    let img = render_scope_buffer(samples, width, height, style, trigger_level, line_width, scale_factor);
+    let buffer = SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(img.as_raw(), width, height);
+    Image::from_rgba8_premultiplied(buffer)
+}
+
+/// Wie `render_scope`, liefert aber das Bild statt eines Slint-Images.
+fn render_scope_buffer(samples: &[f32], width: u32, height: u32, style: &PlotStyle, trigger_level: Option<f32>, line_width: u32, scale_factor: f32) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    let mut img = ImageBuffer::from_pixel(width, height, premultiply(style.background));
+    let center = height as f32 / 2.0;
+    let to_y = |value: f32| center - value.clamp(-1.0, 1.0) * (center - 1.0);
+    let line = scaled(1, scale_factor);
+    draw_row(&mut img, center, line, premultiply(style.grid));
+    if let Some(level) = trigger_level {
+        draw_row(&mut img, to_y(level), line, premultiply(style.peak));
+    }
+
+    let trace = premultiply(style.trace_a);
+    let grow = scaled(line_width.max(1), scale_factor) - line;
+    let samples_per_column = samples.len() as f64 / width.max(1) as f64;
+    let mut previous: Option<f32> = None;
+    for x in 0..width {
+        let from = (x as f64 * samples_per_column).floor() as usize;
+        if from >= samples.len() {
+            break;
+        }
+        let to = (((x + 1) as f64 * samples_per_column).floor() as usize).clamp(from + 1, samples.len());
+        let column = &samples[from..to];
+        let (low, high) = column.iter().chain(previous.as_ref()).fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), &s| (low.min(s), high.max(s)));
+        previous = column.last().copied();
+        // Die Stärke liegt je zur Hälfte vor und hinter der Spalte bzw. über und unter dem Bereich
+        let stroke = (line + grow) as f32;
+        let left = x.saturating_sub(grow / 2);
+        for x in left..(left + line + grow).min(width) {
+            draw_coverage(&mut img, x, to_y(high) - stroke / 2.0, to_y(low) + stroke / 2.0, trace, |_| 1.0);
+        }
+    }
+    img
+}
+
+/// Rendert ein Histogramm als Balken von links nach rechts, die größte Klasse füllt die Höhe.
+/// Zwischen breiten Balken bleibt ein Pixel frei.
+pub fn render_histogram(counts: &[u32], width: u32, height: u32, style: &PlotStyle, scale_factor: f32) -> Image {
+    let mut img = ImageBuffer::from_pixel(width, height, premultiply(style.background));
+    let line = scaled(1, scale_factor);
+    draw_row(&mut img, height.saturating_sub(line) as f32, line, premultiply(style.axis));
+
+    let bar = premultiply(style.trace_a);
+    let highest = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let bins = counts.len().max(1) as u64;
+    for (idx, &count) in counts.iter().enumerate() {
+        let left = (idx as u64 * width as u64 / bins) as u32;
+        let right = ((idx as u64 + 1) * width as u64 / bins) as u32;
+        let gap = if right - left > 2 * line { line } else { 0 };
+        let top = height - (count as f32 / highest * height as f32).round() as u32;
+        draw_vertical(&mut img, left, top, height, right - left - gap, bar);
+    }
+
+    let buffer = SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(img.as_raw(), width, height);
+    Image::from_rgba8_premultiplied(buffer)
+}
+
+/// Anzahl gespeicherter Punkte, die in eine Spalte fallen, wenn `visible_len` Punkte auf `width`
+/// Spalten verteilt werden. Unter 1 wird ein Punkt auf mehrere Spalten gestreckt.
+pub fn points_per_column(visible_len: usize, width: u32) -> f64 {
+    visible_len.max(1) as f64 / width.max(1) as f64
+}
+
+/// Linker Rand des Ausschnitts ab Index `start`, an dem sich die Spalten beim Scrollen nicht
+/// verschieben. `first_index` ist die fortlaufende Nummer des Punktes mit Index 0 (siehe
+/// `RingBuffer::first_index`). Der Rand rückt um weniger als eine Spalte nach rechts auf das
+/// nächste Vielfache von `points_per_column` in diesen Nummern. So fallen in jedem Bild und
+/// über den Umlauf des Verlaufs hinweg dieselben Punkte in eine Spalte, der Verlauf rückt um
+/// ganze Spalten weiter und der neueste Punkt liegt immer in der letzten Spalte.
+pub fn aligned_start(start: i32, first_index: u64, visible_len: usize, width: u32) -> f64 {
+    let (visible_len, width) = (visible_len.max(1) as i128, width.max(1) as i128);
+    // Ganzzahlig gerechnet, damit dieselben Punkte auch bei langer Laufzeit exakt dieselbe
+    // Verschiebung ergeben
+    let first = first_index as i128 + start as i128;
+    let column = (first * width).div_euclid(visible_len) + i128::from((first * width).rem_euclid(visible_len) != 0);
+    start as f64 + (column * visible_len - first * width) as f64 / width as f64
+}
+
+/// Punkte aus `traces`, die in Spalte `x` fallen, wenn der Ausschnitt bei `start` beginnt. Leer
+/// außerhalb von `traces` (z.B. bei negativem `start`). Ein gebrochener `start` verschiebt die
+/// Spaltengrenzen innerhalb der Punkte (siehe `aligned_start`).
+fn column_points<T>(traces: &[T], start: f64, points_per_column: f64, x: u32) -> &[T] {
+    // Liegt eine Grenze rechnerisch genau auf einem Punkt, darf der Rundungsfehler sie nicht
+    // in einem Bild davor und im nächsten dahinter landen lassen
+    let boundary = |x: u32| (start + x as f64 * points_per_column + 1e-6).floor() as i64;
+    let from = boundary(x);
+    let to = boundary(x + 1).max(from + 1);
+    let len = traces.len() as i64;
+    &traces[from.clamp(0, len) as usize..to.clamp(0, len) as usize]
+}
+
+/// Hüllkurve je Spalte bei derselben Aufteilung wie `fit_to_width`: der kleinste der kleinsten
+/// und der größte der größten Werte. Spalten außerhalb des Verlaufs bleiben 0.
+pub fn fit_envelope(envelope: &Envelope, start: f64, visible_len: usize, width: u32) -> Envelope {
+    let points_per_column = points_per_column(visible_len, width);
+    let fit = |trace: &[TracePoint], fold: fn(f32, f32) -> f32| -> Vec<TracePoint> {
+        (0..width)
+            .map(|x| {
+                let points = column_points(trace, start, points_per_column, x);
+                if points.is_empty() {
+                    return (0.0, 0.0);
+                }
+                points.iter().skip(1).fold(points[0], |(l, r), &(left, right)| (fold(l, left), fold(r, right)))
+            })
+            .collect()
+    };
+    Envelope { lows: fit(&envelope.lows, f32::min), highs: fit(&envelope.highs, f32::max) }
+}
+
+/// Verteilt die `visible_len` Punkte ab Index `start` auf `width` Spalten. Fallen mehrere Punkte
+/// in eine Spalte, gilt der größte, sonst wird ein Punkt auf mehrere Spalten gestreckt. Spalten
+/// außerhalb von `traces` bleiben leer.
+pub fn fit_to_width(traces: &[TracePoint], start: f64, visible_len: usize, width: u32) -> Vec<TracePoint> {
+    let points_per_column = points_per_column(visible_len, width);
+    (0..width)
+        .map(|x| column_points(traces, start, points_per_column, x).iter().fold((0.0f32, 0.0f32), |(l, r), &(left, right)| (l.max(left), r.max(right))))
+        .collect()
+}
+
+/// Wie `fit_to_width`, aber mit dem kleinsten Wert je Spalte. Zusammen zeigen beide, wie weit
+/// die Punkte einer Spalte streuen, wenn der Ausschnitt mehr Punkte als Spalten hat.
+pub fn column_minimum(traces: &[TracePoint], start: f64, visible_len: usize, width: u32) -> Vec<TracePoint> {
+    let points_per_column = points_per_column(visible_len, width);
+    (0..width)
+        .map(|x| {
+            let points = column_points(traces, start, points_per_column, x);
+            if points.is_empty() {
+                return (0.0, 0.0);
+            }
+            points.iter().fold((f32::INFINITY, f32::INFINITY), |(l, r), &(left, right)| (l.min(left), r.min(right)))
+        })
+        .collect()
+}
+
+/// Zeit des ersten Punktes je Spalte bei derselben Aufteilung wie `fit_to_width`, NaN für
+/// Spalten ohne Punkt.
+pub fn column_times(times: &[f64], start: f64, visible_len: usize, width: u32) -> Vec<f64> {
+    let points_per_column = points_per_column(visible_len, width);
+    (0..width).map(|x| column_points(times, start, points_per_column, x).first().copied().unwrap_or(f64::NAN)).collect()
+}
+
+/// Spalte, in der Punkt `idx` bei der Aufteilung von `fit_to_width` beginnt, `None` außerhalb.
+pub fn point_column(idx: usize, start: f64, visible_len: usize, width: u32) -> Option<u32> {
+    let offset = idx as f64 - start;
+    let points_per_column = points_per_column(visible_len, width);
+    let x = (offset / points_per_column).ceil();
+    (offset >= 0.0 && x < width as f64).then_some(x as u32)
+}
+
+/// Speichert ein mit `render_plot_buffer` erzeugtes Bild als PNG, das gerades Alpha erwartet.
+pub fn save_png(mut img: ImageBuffer<Rgba<u8>, Vec<u8>>, path: &Path) -> ImageResult<()> {
+    for pixel in img.pixels_mut() {
+        let alpha = pixel[3] as u16;
+        if alpha > 0 && alpha < 255 {
+            for value in pixel.0.iter_mut().take(3) {
+                *value = ((*value as u16 * 255 + alpha / 2) / alpha).min(255) as u8;
+            }
+        }
+    }
+    img.save(path)
+}
+
+/// Zeichnet eine Spalte von `low` bis `high` um `center_y`, beide als Anteil der halben Spurhöhe
+/// mit Vorzeichen (positiv nach oben). Ein Bereich unter einem Pixel wird auf ein Pixel
+/// verbreitert, damit z.B. ein konstanter Chunk als Linie sichtbar bleibt. Angeschnittene Pixel
+/// an den Enden werden nach ihrer Abdeckung eingeblendet (Anti-Aliasing).
+/// Bei `TraceFill::Gradient` wächst die Deckkraft mit dem Abstand zur Mittellinie. Über 1 Pixel
+/// `stroke` werden beide Enden um die zusätzliche Stärke verlängert, begrenzt auf das Bild.
+#[allow(clippy::too_many_arguments)]
+fn draw_span(img: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, x: u32, center_y: f32, low: f32, high: f32, lane_height: f32, stroke: u32, color: Rgba<u8>, fill: TraceFill) {
+    if high < low {
+        return;
+    }
+    // In ganzen Pixeln, damit die Ränder nicht unscharf werden, bei gerader Stärke unten eins mehr
+    let grow_top = stroke.saturating_sub(1) / 2;
+    let grow_bottom = stroke.saturating_sub(1) - grow_top;
+    let mut top = center_y - high.clamp(-1.0, 1.0) * lane_height / 2.0 - grow_top as f32;
+    let mut bottom = center_y - low.clamp(-1.0, 1.0) * lane_height / 2.0 + grow_bottom as f32;
+    if bottom - top < stroke as f32 {
+        let middle = (top + bottom) / 2.0;
+        (top, bottom) = (middle - stroke as f32 / 2.0, middle + stroke as f32 / 2.0);
+    }
+    // Auch eine verbreiterte Spalte bleibt in ihrer Spur
+    let top = top.max(center_y - lane_height / 2.0);
+    let bottom = bottom.min(center_y + lane_height / 2.0);
+    draw_coverage(img, x, top, bottom, color, |y| match fill {
+        TraceFill::Solid => 1.0,
+        TraceFill::Gradient => {
+            let distance = ((y as f32 + 0.5 - center_y).abs() / (lane_height / 2.0)).min(1.0);
+            GRADIENT_MIN_ALPHA + (1.0 - GRADIENT_MIN_ALPHA) * distance
+        }
+    });
+}
+
+/// Blendet `color` in Spalte `x` von `top` bis `bottom` (Pixelzeilen, gebrochen) mit der Deckkraft
+/// `intensity(y)` ein. Angeschnittene Pixel an den Enden zählen nur mit ihrer Abdeckung, statt
+/// auf ganze Pixel abgeschnitten zu werden (Anti-Aliasing).
+fn draw_coverage(img: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, x: u32, top: f32, bottom: f32, color: Rgba<u8>, intensity: impl Fn(u32) -> f32) {
+    let top = top.max(0.0);
+    let bottom = bottom.min(img.height() as f32);
+    if x >= img.width() || bottom <= top {
+        return;
+    }
+    for y in top.floor() as u32..(bottom.ceil() as u32).min(img.height()) {
+        let coverage = (bottom.min(y as f32 + 1.0) - top.max(y as f32)).clamp(0.0, 1.0);
+        let pixel = img.get_pixel_mut(x, y);
+        *pixel = blend(*pixel, color, coverage * intensity(y));
+    }
+}
+
+/// Zeichnet an beide Enden einer Spalte mit dem Anteil `fraction` der halben Spurhöhe eine
+/// Markierung, `thickness` Pixel stark nach innen.
+fn draw_peak_marker(img: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, x: u32, center_y: f32, fraction: f32, lane_height: f32, thickness: u32, color: Rgba<u8>) {
+    if fraction <= 0.0 {
+        return;
+    }
+    let half = fraction.min(1.0) * lane_height / 2.0;
+    let thickness = thickness as f32;
+    draw_coverage(img, x, center_y - half, center_y - half + thickness, color, |_| 1.0);
+    draw_coverage(img, x, center_y + half - thickness, center_y + half, color, |_| 1.0);
+}
+
+/// Zeichnet die Zeitachse unterhalb von `top` mit Markierungen in Sekunden relativ zu `newest_x`,
+/// mit `column_ages` (siehe `TimeAxis`) an der Spalte mit dem passenden Alter.
+/// Abstände und Schrift wachsen mit `scale_factor`.
+fn draw_time_axis(img: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, top: u32, newest_x: i64, seconds_per_column: f32, column_ages: Option<&[f32]>, scale_factor: f32, color: Rgba<u8>) {
+    if seconds_per_column <= 0.0 || top >= img.height() {
+        return;
+    }
+    let line = scaled(1, scale_factor);
+    let glyph_scale = scaled(GLYPH_SCALE, scale_factor);
+    draw_row(img, top as f32, line, color);
+
+    // Kleinsten Abstand wählen, bei dem die Beschriftungen mindestens 60 logische Pixel auseinanderliegen
+    let min_step = 60.0 * scale_factor * seconds_per_column;
+    let step = [0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0, 600.0]
+        .into_iter()
+        .find(|&step| step >= min_step)
+        .unwrap_or(min_step.ceil());
+
+    for tick in 0.. {
+        let seconds = tick as f32 * step;
+        let x = match column_ages {
+            Some(ages) => {
+                // Die rechteste Spalte, die mindestens so alt ist. Ist sie mehr als eine Spalte
+                // älter, liegt die Zeit in einer Lücke oder rechts außerhalb des Bildes.
+                let Some(x) = ages.iter().rposition(|&age| age >= seconds) else {
+                    break;
+                };
+                if ages[x] > seconds + seconds_per_column {
+                    continue;
+                }
+                x as i64
+            }
+            None => newest_x - (seconds / seconds_per_column).round() as i64,
+        };
+        if x < 0 {
+            break;
+        }
+        if x >= img.width() as i64 {
+            continue;
+        }
+        draw_vertical(img, x as u32, top, top + scaled(3, scale_factor), line, color);
+        let label = if tick == 0 {
+            String::from("0s")
+        } else if step < 1.0 {
+            format!("-{:.1}s", seconds)
+        } else {
+            format!("-{}s", seconds.round() as i64)
+        };
+        // Beschriftung unter der Markierung zentrieren, am Bildrand nach innen schieben
+        let label_width = label.len() as i64 * (GLYPH_WIDTH as i64 + 1) * glyph_scale as i64;
+        let label_x = (x - label_width / 2).clamp(0, (img.width() as i64 - label_width).max(0));
+        draw_text(img, label_x as u32, top + scaled(4, scale_factor), &label, glyph_scale, color);
+    }
+}
+
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+/// Kantenlänge eines Glyphen-Bits in logischen Pixeln
+const GLYPH_SCALE: u32 = 2;
+
+/// 3x5-Bitmap-Glyphen für die Achsenbeschriftung, je Zeile 3 Bit (MSB links)
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT as usize] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        's' => [0b000, 0b011, 0b100, 0b001, 0b110],
+        _ => [0; GLYPH_HEIGHT as usize],
+    }
+}
+
+/// Schreibt `text` mit der Bitmap-Schrift ab der linken oberen Ecke `x`, `y`, jedes Bit der Glyphe
+/// als Quadrat mit `scale` Pixeln Kantenlänge.
+fn draw_text(img: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, x: u32, y: u32, text: &str, scale: u32, color: Rgba<u8>) {
+    for (i, c) in text.chars().enumerate() {
+        let glyph_x = x + i as u32 * (GLYPH_WIDTH + 1) * scale;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let px = glyph_x + column * scale + dx;
+                        let py = y + row as u32 * scale + dy;
+                        if px < img.width() && py < img.height() {
+                            let pixel = img.get_pixel_mut(px, py);
+                            *pixel = blend(*pixel, color, 1.0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Zeichnet eine horizontale Linie ab der Pixelzeile, in der `y` liegt, `thickness` Pixel stark.
+fn draw_row(img: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, y: f32, thickness: u32, color: Rgba<u8>) {
+    if y < 0.0 || y >= img.height() as f32 {
+        return;
+    }
+    let top = y as u32;
+    for y in top..(top + thickness).min(img.height()) {
+        for x in 0..img.width() {
+            let pixel = img.get_pixel_mut(x, y);
+            *pixel = blend(*pixel, color, 1.0);
+        }
+    }
+}
+
+/// Zeichnet eine senkrechte Linie ab Spalte `x` von Zeile `top` bis vor `bottom`, `thickness`
+/// Pixel stark.
+fn draw_vertical(img: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, x: u32, top: u32, bottom: u32, thickness: u32, color: Rgba<u8>) {
+    for x in x..(x + thickness).min(img.width()) {
+        for y in top..bottom.min(img.height()) {
+            let pixel = img.get_pixel_mut(x, y);
+            *pixel = blend(*pixel, color, 1.0);
+        }
+    }
+}
+
+/// Länge von `pixels` logischen Pixeln in physischen Pixeln, mindestens 1.
+fn scaled(pixels: u32, scale_factor: f32) -> u32 {
+    (pixels as f32 * scale_factor).round().max(1.0) as u32
+}
+
+/// Wandelt eine Slint-Farbe in eine vormultiplizierte RGBA-Farbe um.
+fn premultiply(color: Color) -> Rgba<u8> {
+    let alpha = color.alpha() as u16;
+    let channel = |value: u8| ((value as u16 * alpha + 127) / 255) as u8;
+    Rgba([channel(color.red()), channel(color.green()), channel(color.blue()), color.alpha()])
+}
+
+/// Vormultiplizierte Farbe mit der Deckkraft `alpha` multipliziert.
+fn faded(color: Rgba<u8>, alpha: f32) -> Rgba<u8> {
+    Rgba(color.0.map(|channel| (channel as f32 * alpha).round() as u8))
+}
+
+/// Legt die vormultiplizierte Farbe `src` mit der Abdeckung `coverage` über `dst`.
+fn blend(dst: Rgba<u8>, src: Rgba<u8>, coverage: f32) -> Rgba<u8> {
+    let src_alpha = src[3] as f32 / 255.0 * coverage;
+    let mut out = [0u8; 4];
+    for (i, value) in out.iter_mut().enumerate() {
+        *value = (src[i] as f32 * coverage + dst[i] as f32 * (1.0 - src_alpha)).round().min(255.0) as u8;
+    }
+    Rgba(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const HALF_RED: [u8; 4] = [128, 0, 0, 255];
+
+    fn column(img: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Vec<[u8; 4]> {
+        (0..img.height()).map(|y| img.get_pixel(0, y).0).collect()
+    }
+
+    #[test]
+    fn partially_covered_pixels_are_blended() {
+        // 0,5 bis 2,5: die angeschnittenen Pixel an den Enden zählen zur Hälfte
+        let mut img = ImageBuffer::from_pixel(1, 4, BLACK);
+        draw_coverage(&mut img, 0, 0.5, 2.5, RED, |_| 1.0);
+        assert_eq!(column(&img), [HALF_RED, RED.0, HALF_RED, BLACK.0]);
+
+        // Halb deckendes Rot, vormultipliziert, über Schwarz
+        assert_eq!(blend(BLACK, Rgba([128, 0, 0, 128]), 1.0), Rgba(HALF_RED));
+    }
+
+    #[test]
+    fn peak_markers_are_anti_aliased() {
+        // Spur von 0 bis 8, 3/8 der halben Höhe: Markierungen von 2,5 bis 3,5 und 4,5 bis 5,5
+        let mut img = ImageBuffer::from_pixel(1, 8, BLACK);
+        draw_peak_marker(&mut img, 0, 4.0, 0.375, 8.0, 1, RED);
+        let black = BLACK.0;
+        assert_eq!(column(&img), [black, black, HALF_RED, HALF_RED, HALF_RED, HALF_RED, black, black]);
+    }
+
+    #[test]
+    fn scope_strokes_are_anti_aliased() {
+        let gray = Color::from_rgb_u8(128, 128, 128);
+        let style = PlotStyle { background: Color::from_rgb_u8(0, 0, 0), trace_a: Color::from_rgb_u8(255, 0, 0), grid: gray, ..Default::default() };
+        // 0.5 liegt bei 10 Pixeln Höhe in Zeile 3, der Strich reicht von 2,5 bis 3,5; die
+        // Mittellinie liegt in Zeile 5
+        let img = render_scope_buffer(&[0.5; 4], 1, 10, &style, None, 1, 1.0);
+        let (black, gray) = (BLACK.0, [128, 128, 128, 255]);
+        assert_eq!(column(&img), [black, black, HALF_RED, HALF_RED, black, gray, black, black, black, black]);
+    }
+}
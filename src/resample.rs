@@ -0,0 +1,169 @@
+// Umrechnung des Eingangs auf eine feste interne Abtastrate, damit Zeitachse und Chunk-Dauer nicht
+// von der nativen Rate des Geräts abhängen. Interpoliert wird mit einem gefensterten Sinc, dessen
+// Grenzfrequenz beim Heruntertakten mitwandert, damit keine Spiegelfrequenzen in die Pegel
+// einfließen. Der Zustand bleibt zwischen den Puffern erhalten, ein Puffer darf auch mitten in
+// einem Frame enden.
+
+use std::f64::consts::PI;
+use std::time::Duration;
+
+/// Stützstellen je Seite des Filterkerns beim Heraufsetzen, beim Heruntertakten entsprechend mehr
+const TAPS_PER_SIDE: usize = 16;
+
+/// Grenzfrequenz relativ zur kleineren Nyquist-Frequenz, etwas darunter für den Übergangsbereich
+const ROLLOFF: f64 = 0.95;
+
+pub struct Resampler {
+    channels: usize,
+    /// Eingangs-Frames je Ausgangs-Frame
+    step: f64,
+    /// Grenzfrequenz in Zyklen je Eingangs-Sample
+    cutoff: f64,
+    /// Halbe Länge des Filterkerns in Eingangs-Frames, zugleich die Verzögerung
+    half: usize,
+    input_rate: u32,
+    /// Interleavter Eingang ab dem ältesten noch benötigten Frame, am Ende ggf. ein unvollständiges Frame
+    history: Vec<f32>,
+    /// Zeitpunkt des nächsten Ausgangs-Frames in Frames ab dem Anfang von `history`
+    position: f64,
+    /// Gewichte des Filterkerns für den aktuellen Ausgangs-Frame
+    weights: Vec<f32>,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32, channels: usize) -> Self {
+        let step = input_rate.max(1) as f64 / output_rate.max(1) as f64;
+        let half = half_taps(step);
+        let mut resampler = Resampler {
+            channels: channels.max(1),
+            step,
+            cutoff: 0.5 * ROLLOFF / step.max(1.0),
+            half,
+            input_rate: input_rate.max(1),
+            history: Vec::new(),
+            position: 0.0,
+            weights: Vec::with_capacity(2 * half),
+        };
+        resampler.reset();
+        resampler
+    }
+
+    /// Verzögerung durch den Filterkern: der Ausgang hinkt dem Eingang um `half` Frames hinterher.
+    pub fn latency(&self) -> Duration {
+        Duration::from_secs_f64(self.half as f64 / self.input_rate as f64)
+    }
+
+    /// Verzögerung eines Resamplers für diese Raten, ohne ihn anzulegen.
+    pub fn delay(input_rate: u32, output_rate: u32) -> Duration {
+        let input_rate = input_rate.max(1);
+        Duration::from_secs_f64(half_taps(input_rate as f64 / output_rate.max(1) as f64) as f64 / input_rate as f64)
+    }
+
+    /// Verwirft den Eingang, z. B. nach einer Pause. Der Verlauf beginnt wieder mit Stille, nur ein
+    /// angefangenes Frame bleibt, damit die folgenden Samples ihren Kanal behalten.
+    pub fn reset(&mut self) {
+        let whole = self.history.len() - self.history.len() % self.channels;
+        self.history.drain(..whole);
+        // Stille vor dem ersten Frame, damit schon der erste Ausgang einen vollständigen Kern hat
+        self.history.splice(0..0, std::iter::repeat_n(0.0, self.half * self.channels));
+        self.position = self.half as f64;
+    }
+
+    /// Nimmt interleavte Samples auf und hängt alle damit vollständig berechenbaren Ausgangs-Frames
+    /// an `output` an. `output` erhält immer ganze Frames.
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        let channels = self.channels;
+        self.history.extend_from_slice(input);
+        let frames = self.history.len() / channels;
+        // Jeder Ausgang braucht `half` Frames nach seinem Zeitpunkt
+        while (self.position.floor() as usize) + self.half < frames {
+            let center = self.position.floor() as usize;
+            let first = center + 1 - self.half;
+            self.weights.clear();
+            let mut sum = 0.0;
+            for n in first..=center + self.half {
+                let x = self.position - n as f64;
+                let weight = kernel(x, self.cutoff, self.half as f64);
+                sum += weight;
+                self.weights.push(weight as f32);
+            }
+            // Auf eine Verstärkung von genau 1 normieren, damit Gleichanteil und Pegel erhalten bleiben
+            let norm = if sum.abs() > 1e-12 { (1.0 / sum) as f32 } else { 0.0 };
+            for channel in 0..channels {
+                let value: f32 = self.weights.iter().enumerate().map(|(tap, weight)| weight * self.history[(first + tap) * channels + channel]).sum();
+                output.push(value * norm);
+            }
+            self.position += self.step;
+        }
+        // Frames vor dem Kern des nächsten Ausgangs werden nicht mehr gebraucht
+        let consumed = (self.position.floor() as usize + 1).saturating_sub(self.half).min(frames);
+        self.history.drain(..consumed * channels);
+        self.position -= consumed as f64;
+    }
+}
+
+/// Halbe Kernlänge in Eingangs-Frames für `step` Eingangs-Frames je Ausgangs-Frame.
+fn half_taps(step: f64) -> usize {
+    (TAPS_PER_SIDE as f64 * step.max(1.0)).ceil() as usize
+}
+
+/// Gefensterter Sinc mit Hann-Fenster über `half` Frames je Seite.
+fn kernel(x: f64, cutoff: f64, half: f64) -> f64 {
+    if x.abs() >= half {
+        return 0.0;
+    }
+    let window = 0.5 * (1.0 + (PI * x / half).cos());
+    let arg = 2.0 * cutoff * x;
+    let sinc = if arg.abs() < 1e-9 { 1.0 } else { (PI * arg).sin() / (PI * arg) };
+    2.0 * cutoff * sinc * window
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resampling_keeps_levels_and_channel_alignment() {
+        // 1 kHz von 44,1 kHz auf 48 kHz, beide Kanäle mit eigener Amplitude
+        let sine: Vec<f32> = (0..4410)
+            .flat_map(|i| {
+                let s = (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / 44100.0).sin();
+                [s, 0.5 * s]
+            })
+            .collect();
+        let mut whole = Vec::new();
+        Resampler::new(44100, 48000, 2).process(&sine, &mut whole);
+        // 0,1 s bei 48 kHz, abzüglich der Verzögerung des Filterkerns
+        assert!((4700..4800).contains(&(whole.len() / 2)), "{}", whole.len());
+        let peak = |channel: usize| whole.iter().skip(200 + channel).step_by(2).fold(0f32, |max, &s| max.max(s));
+        assert!((peak(0) - 1.0).abs() < 0.01 && (peak(1) - 0.5).abs() < 0.005, "{} {}", peak(0), peak(1));
+
+        // Puffer, die mitten im Frame enden, ergeben dasselbe wie ein Stück
+        let mut resampler = Resampler::new(44100, 48000, 2);
+        let mut pieces = Vec::new();
+        let mut offset = 0;
+        for len in [7, 13, 1, 1000, 2, 11].iter().cycle() {
+            if offset >= sine.len() {
+                break;
+            }
+            let end = (offset + len).min(sine.len());
+            resampler.process(&sine[offset..end], &mut pieces);
+            offset = end;
+        }
+        assert_eq!(pieces.len(), whole.len());
+        assert!(pieces.iter().zip(&whole).all(|(a, b)| (a - b).abs() < 1e-5));
+    }
+
+    #[test]
+    fn reset_keeps_a_started_frame() {
+        // Gleichanteil 1,0 links und 0,5 rechts, der erste Puffer endet mitten im Frame
+        let constant: Vec<f32> = (0..2000).flat_map(|_| [1.0, 0.5]).collect();
+        let mut resampler = Resampler::new(44100, 48000, 2);
+        let mut output = Vec::new();
+        resampler.process(&constant[..5], &mut output);
+        resampler.reset();
+        resampler.process(&constant[5..], &mut output);
+        let last = &output[output.len() - 2..];
+        assert!((last[0] - 1.0).abs() < 1e-3 && (last[1] - 0.5).abs() < 1e-3, "{:?}", last);
+    }
+}
@@ -0,0 +1,146 @@
+// Verlauf fester Länge für die Punkte im UI-Thread. Neue Werte überschreiben die ältesten, die
+// Reihenfolge bleibt dabei erhalten. Der Typ der Punkte ist ein Parameter, damit dieselbe
+// Verwaltung für Pegel, Hüllkurven oder andere Werte je Punkt dient.
+
+use std::iter::Chain;
+use std::slice;
+
+/// Iterator über einen `RingBuffer` vom ältesten zum neuesten Wert.
+pub type Iter<'a, T> = Chain<slice::Iter<'a, T>, slice::Iter<'a, T>>;
+
+pub struct RingBuffer<T> {
+    /// Gespeicherte Werte; ist der Puffer voll, liegt der älteste bei `head`
+    items: Vec<T>,
+    /// Index des ältesten Wertes in `items`
+    head: usize,
+    /// Höchstens so viele Werte werden behalten
+    capacity: usize,
+    /// Seit dem letzten `clear` angehängte Werte
+    pushed: u64,
+}
+
+impl<T> RingBuffer<T> {
+    /// Leerer Puffer für höchstens `capacity` Werte (mindestens einen). Der Speicher wird sofort
+    /// reserviert, `push` allokiert danach nicht mehr.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        RingBuffer { items: Vec::with_capacity(capacity), head: 0, capacity, pushed: 0 }
+    }
+
+    /// Hängt `value` als neuesten Wert an, bei vollem Puffer ersetzt er den ältesten.
+    pub fn push(&mut self, value: T) {
+        self.pushed += 1;
+        if self.items.len() < self.capacity {
+            self.items.push(value);
+        } else {
+            self.items[self.head] = value;
+            self.head = (self.head + 1) % self.capacity;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Fortlaufende Nummer des ältesten Wertes, gezählt ab dem ersten `push` nach `clear`. Sie
+    /// ändert sich nicht, wenn der Puffer umläuft, und identifiziert so einen Wert über Umläufe
+    /// hinweg.
+    pub fn first_index(&self) -> u64 {
+        self.pushed - self.items.len() as u64
+    }
+
+    /// Ändert die Höchstzahl der Werte (mindestens einer). Beim Verkleinern bleiben die neuesten
+    /// Werte erhalten.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        let capacity = capacity.max(1);
+        if capacity == self.capacity {
+            return;
+        }
+        // Den ältesten Wert an den Anfang holen, danach gilt die Reihenfolge wieder ohne Umlauf
+        self.items.rotate_left(self.head);
+        self.head = 0;
+        let excess = self.items.len().saturating_sub(capacity);
+        self.items.drain(..excess);
+        self.items.reserve_exact(capacity - self.items.len());
+        self.capacity = capacity;
+    }
+
+    /// Verwirft alle Werte, die Höchstzahl bleibt.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.head = 0;
+        self.pushed = 0;
+    }
+
+    /// Die Werte vom ältesten zum neuesten als zwei zusammenhängende Abschnitte, der zweite ist
+    /// leer, solange der Puffer nicht umgelaufen ist.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (newer, older) = self.items.split_at(self.head);
+        (older, newer)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (older, newer) = self.as_slices();
+        older.iter().chain(newer.iter())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a RingBuffer<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_buffer_wraps_around_in_order() {
+        let mut buffer = RingBuffer::new(4);
+        for value in 0..6 {
+            buffer.push(value);
+        }
+        // Die beiden ältesten Werte sind überschrieben, die Reihenfolge bleibt
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.as_slices(), (&[2, 3][..], &[4, 5][..]));
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [2, 3, 4, 5]);
+        assert_eq!(buffer.iter().rev().take(2).copied().collect::<Vec<_>>(), [5, 4]);
+        // Die Nummer des ältesten Wertes zählt über den Umlauf hinweg weiter
+        assert_eq!(buffer.first_index(), 2);
+
+        buffer.clear();
+        assert_eq!(buffer.len(), 0);
+        buffer.push(7);
+        assert_eq!(buffer.as_slices(), (&[7][..], &[][..]));
+        assert_eq!(buffer.first_index(), 0);
+    }
+
+    #[test]
+    fn ring_buffer_capacity_bounds_the_history() {
+        let mut buffer = RingBuffer::new(5);
+        for value in 0..8 {
+            buffer.push(value);
+        }
+        // Verkleinern behält die neuesten Werte, auch wenn der Puffer umgelaufen ist
+        buffer.set_capacity(3);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [5, 6, 7]);
+        buffer.push(8);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [6, 7, 8]);
+
+        // Vergrößern verliert nichts und füllt erst auf, bevor wieder überschrieben wird
+        buffer.set_capacity(5);
+        buffer.push(9);
+        buffer.push(10);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [6, 7, 8, 9, 10]);
+        buffer.push(11);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [7, 8, 9, 10, 11]);
+
+        // Mindestens ein Wert
+        buffer.set_capacity(0);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [11]);
+    }
+}
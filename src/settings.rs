@@ -0,0 +1,150 @@
+// Einstellungen, die zwischen zwei Starts erhalten bleiben. Gespeichert als TOML im
+// Konfigurationsverzeichnis der Plattform; fehlt die Datei oder ist sie fehlerhaft, gelten die
+// Standardwerte.
+
+use std::fs;
+use std::path::PathBuf;
+
+use directories::ProjectDirs;
+use rmnc::capture::DEFAULT_BUFFER_DURATION;
+use serde::{Deserialize, Serialize};
+
+use crate::{DEFAULT_CHUNK_SIZE, DEFAULT_HISTORY_LEN, DEFAULT_REFRESH_INTERVAL};
+
+/// Gespeicherte Einstellungen. Fehlende Felder erhalten ihren Standardwert, damit ältere Dateien
+/// lesbar bleiben.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Name des zuletzt geöffneten Geräts
+    pub device: Option<String>,
+    pub loopback: bool,
+    /// Gewünschte Dauer eines Audio-Puffers in Millisekunden
+    pub buffer_ms: u32,
+    pub chunk_size: usize,
+    pub history_len: usize,
+    pub gain_db: f32,
+    /// Wie `scale-mode` in der UI
+    pub scale_mode: i32,
+    pub auto_attack_ms: i32,
+    pub auto_release_ms: i32,
+    pub plot_style: i32,
+    pub image_plot: bool,
+    pub db_scale: bool,
+    pub db_floor: f32,
+    pub grid: bool,
+    pub gradient: bool,
+    pub time_axis: bool,
+    pub peak_hold: bool,
+    pub column_range: bool,
+    /// Stärke der Spuren im gerasterten Plot und im Oszilloskop in logischen Pixeln
+    pub line_width: i32,
+    /// Wie `plot-layout` in der UI
+    pub plot_layout: i32,
+    /// Wie `colormap` in der UI
+    pub colormap: i32,
+    /// Wie `smoothing-mode` in der UI
+    pub smoothing_mode: i32,
+    pub smoothing_window: i32,
+    pub peak_decay_db: f32,
+    pub dc_filter: bool,
+    pub dc_cutoff_hz: i32,
+    pub gate: bool,
+    pub gate_threshold_db: i32,
+    pub gate_hold_ms: i32,
+    pub gate_release_ms: i32,
+    pub envelope: bool,
+    pub envelope_attack_ms: i32,
+    pub envelope_release_ms: i32,
+    pub clip_trigger: bool,
+    pub clip_threshold_db: i32,
+    pub clip_pre_roll_s: i32,
+    pub clip_length_s: i32,
+    pub refresh_ms: i32,
+}
+
+impl Default for Settings {
+    /// Entspricht den Vorgaben in `app-window.slint`
+    fn default() -> Self {
+        Settings {
+            device: None,
+            loopback: false,
+            buffer_ms: DEFAULT_BUFFER_DURATION.as_millis() as u32,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            history_len: DEFAULT_HISTORY_LEN,
+            gain_db: 0.0,
+            scale_mode: 0,
+            auto_attack_ms: 50,
+            auto_release_ms: 1000,
+            plot_style: 0,
+            image_plot: false,
+            db_scale: false,
+            db_floor: -72.0,
+            grid: true,
+            gradient: false,
+            time_axis: true,
+            peak_hold: false,
+            column_range: false,
+            line_width: 1,
+            plot_layout: 0,
+            colormap: 0,
+            smoothing_mode: 0,
+            smoothing_window: 8,
+            peak_decay_db: 12.0,
+            dc_filter: false,
+            dc_cutoff_hz: 10,
+            gate: false,
+            gate_threshold_db: -60,
+            gate_hold_ms: 50,
+            gate_release_ms: 100,
+            envelope: false,
+            envelope_attack_ms: 10,
+            envelope_release_ms: 100,
+            clip_trigger: false,
+            clip_threshold_db: -20,
+            clip_pre_roll_s: 2,
+            clip_length_s: 10,
+            refresh_ms: DEFAULT_REFRESH_INTERVAL.as_millis() as i32,
+        }
+    }
+}
+
+impl Settings {
+    /// Liest die gespeicherten Einstellungen, bei fehlender oder fehlerhafter Datei die Standardwerte.
+    pub fn load() -> Settings {
+        let Some(path) = settings_path() else {
+            return Settings::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => Settings::parse(&text).unwrap_or_else(|err| {
+                eprintln!("Ignoring malformed settings in {}: {}", path.display(), err);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Settings, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    /// Schreibt die Einstellungen, Fehler werden nur gemeldet.
+    pub fn save(&self) {
+        let Some(path) = settings_path() else {
+            return;
+        };
+        let result = toml::to_string(self).map_err(|err| err.to_string()).and_then(|text| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+            }
+            fs::write(&path, text).map_err(|err| err.to_string())
+        });
+        if let Err(err) = result {
+            eprintln!("Error saving settings to {}: {}", path.display(), err);
+        }
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "audioprog", "rmnc").map(|dirs| dirs.config_dir().join("settings.toml"))
+}
@@ -0,0 +1,44 @@
+// Erkennung, ob am Eingang ein Signal anliegt. Unterscheidet Stille von einem stehengebliebenen
+// Stream, die im Plot beide als Mittellinie erscheinen.
+
+use std::time::Duration;
+
+/// Pegel, ab dem ein Signal als vorhanden gilt
+pub const ON_DB: f32 = -60.0;
+/// Pegel, unter den das Signal fallen muss, damit es als verschwunden gilt (Hysterese)
+pub const OFF_DB: f32 = -66.0;
+/// So lange muss das Signal unter `OFF_DB` bleiben, damit die Anzeige ausgeht
+pub const RELEASE: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+pub struct SignalDetector {
+    present: bool,
+    /// Dauer, die das Signal bereits unter `OFF_DB` liegt
+    quiet_for: Duration,
+}
+
+impl SignalDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Übernimmt den größten Wert `level` (±1.0 = 0 dBFS) der Punkte, die in den letzten
+    /// `elapsed` angekommen sind, 0 wenn keine kamen. Liefert, ob ein Signal anliegt.
+    pub fn update(&mut self, level: f32, elapsed: Duration) -> bool {
+        let level_db = 20.0 * level.max(f32::MIN_POSITIVE).log10();
+        if level_db >= ON_DB || (self.present && level_db >= OFF_DB) {
+            self.present = true;
+            self.quiet_for = Duration::ZERO;
+        } else {
+            self.quiet_for += elapsed;
+            if self.quiet_for >= RELEASE {
+                self.present = false;
+            }
+        }
+        self.present
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,71 @@
+// SIMD-Pfad für die Peak-Reduktion, nur mit dem Feature `simd` (Crate `wide`, läuft auf stabilem
+// Rust). Statt jeden Kanal mit `step_by` einzeln und mit Sprüngen durch den Chunk zu lesen, wird
+// der Chunk einmal der Reihe nach in Blöcken von `LANES` Samples gelesen. Teilt die Kanalzahl
+// `LANES`, gehört jede Lane immer zum selben Kanal, die Blöcke sind also schon nach Kanälen
+// aufgeteilt und Minimum und Maximum laufen für alle Kanäle parallel. Am Ende werden die Lanes
+// je Kanal zusammengefasst. Andere Kanalzahlen bleiben beim skalaren Pfad.
+
+use wide::f32x8;
+
+use crate::MAX_CHANNELS;
+
+/// Samples je Vektor
+const LANES: usize = 8;
+
+/// Kleinster und größter Wert je Kanal wie bei `WaveformMode::Peak`, oder `None`, wenn die
+/// Kanalzahl nicht zu den Lanes passt. Kanäle ohne Samples ergeben wie skalar 0.
+pub fn peak(chunk: &[f32], channels: usize) -> Option<([f32; MAX_CHANNELS], [f32; MAX_CHANNELS])> {
+    if channels == 0 || LANES % channels != 0 {
+        return None;
+    }
+    let mut min = f32x8::splat(f32::INFINITY);
+    let mut max = f32x8::splat(f32::NEG_INFINITY);
+    let blocks = chunk.chunks_exact(LANES);
+    let tail = blocks.remainder();
+    for block in blocks {
+        let block = f32x8::new(block.try_into().unwrap_or_default());
+        min = min.min(block);
+        max = max.max(block);
+    }
+
+    let mut channel_min = [f32::INFINITY; MAX_CHANNELS];
+    let mut channel_max = [f32::NEG_INFINITY; MAX_CHANNELS];
+    // Lane i gehört zu Kanal i % channels, da jeder Block bei einem Frame-Anfang beginnt
+    for (lane, (lane_min, lane_max)) in min.to_array().into_iter().zip(max.to_array()).enumerate() {
+        channel_min[lane % channels] = channel_min[lane % channels].min(lane_min);
+        channel_max[lane % channels] = channel_max[lane % channels].max(lane_max);
+    }
+    // Der Rest hinter dem letzten vollen Block beginnt ebenfalls bei einem Frame-Anfang
+    for (idx, &sample) in tail.iter().enumerate() {
+        channel_min[idx % channels] = channel_min[idx % channels].min(sample);
+        channel_max[idx % channels] = channel_max[idx % channels].max(sample);
+    }
+    for (min, max) in channel_min.iter_mut().zip(channel_max.iter_mut()) {
+        if *min > *max {
+            (*min, *max) = (0.0, 0.0);
+        }
+    }
+    Some((channel_min, channel_max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reduce_chunk, reduce_chunk_scalar, WaveformMode};
+
+    #[test]
+    fn simd_peak_matches_scalar() {
+        // Ungerade Längen prüfen auch den Rest hinter dem letzten vollen Vektor
+        for channels in 1..=MAX_CHANNELS {
+            for frames in [0, 1, 3, 7, 64, 1021] {
+                let chunk: Vec<f32> = (0..frames * channels).map(|idx| ((idx * 7919 % 1000) as f32 - 499.5) / 500.0).collect();
+                let scalar = reduce_chunk_scalar(&chunk, channels, WaveformMode::Peak, false);
+                assert_eq!(reduce_chunk(&chunk, channels, WaveformMode::Peak, false), scalar, "{} channels, {} frames", channels, frames);
+                match peak(&chunk, channels) {
+                    Some((min, max)) => assert_eq!((min, max), (scalar.min, scalar.max)),
+                    None => assert!(![1, 2, 4, 8].contains(&channels)),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+// Glättung der Hüllkurve für die Anzeige, z. B. zur ruhigen Pegelbeobachtung. Die gespeicherten
+// Punkte bleiben unverändert, damit Pegel, Spitzenwerte und Onsets weiter mit den echten Werten
+// arbeiten. Geglättet wird in jedem Frame über den ganzen Verlauf, ein Zustand zwischen den
+// Frames ist daher nicht nötig.
+
+use crate::render_plot::{Envelope, TracePoint};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Smoothing {
+    Off,
+    /// Mittelwert der letzten `n` Punkte
+    MovingAverage(usize),
+    /// Exponentielle Glättung mit einer Zeitkonstante von `n` Punkten
+    Exponential(usize),
+}
+
+/// Glättet beide Spuren von `traces` gemäß `smoothing`, `None` ohne Glättung oder bei einem
+/// Fenster von einem Punkt.
+pub fn smooth(traces: &[TracePoint], smoothing: Smoothing) -> Option<Vec<TracePoint>> {
+    match smoothing {
+        Smoothing::MovingAverage(window) if window > 1 => Some(moving_average(traces, window)),
+        Smoothing::Exponential(window) if window > 1 => Some(exponential(traces, window)),
+        _ => None,
+    }
+}
+
+/// Glättet die Hüllkurve wie `smooth`, kleinste und größte Werte getrennt.
+pub fn smooth_envelope(envelope: &Envelope, smoothing: Smoothing) -> Option<Envelope> {
+    Some(Envelope { lows: smooth(&envelope.lows, smoothing)?, highs: smooth(&envelope.highs, smoothing)? })
+}
+
+/// Gleitender Mittelwert über die letzten `window` Punkte, am Anfang über die vorhandenen. Die
+/// Summen laufen in f64 mit, damit sich über lange Verläufe kein Rundungsfehler ansammelt.
+fn moving_average(traces: &[TracePoint], window: usize) -> Vec<TracePoint> {
+    let (mut sum_a, mut sum_b) = (0.0f64, 0.0f64);
+    traces
+        .iter()
+        .enumerate()
+        .map(|(idx, &(a, b))| {
+            sum_a += a as f64;
+            sum_b += b as f64;
+            if idx >= window {
+                let (old_a, old_b) = traces[idx - window];
+                sum_a -= old_a as f64;
+                sum_b -= old_b as f64;
+            }
+            let count = (idx + 1).min(window) as f64;
+            ((sum_a / count) as f32, (sum_b / count) as f32)
+        })
+        .collect()
+}
+
+/// Einpoliger Tiefpass, der beim ersten Punkt beginnt.
+fn exponential(traces: &[TracePoint], window: usize) -> Vec<TracePoint> {
+    let alpha = 1.0 / window as f32;
+    let mut state = traces.first().copied().unwrap_or((0.0, 0.0));
+    traces
+        .iter()
+        .map(|&(a, b)| {
+            state.0 += alpha * (a - state.0);
+            state.1 += alpha * (b - state.1);
+            state
+        })
+        .collect()
+}
@@ -0,0 +1,117 @@
+// Spektrogramm: gefensterte FFT über die Mono-Summe der Eingangs-Samples. Jede FFT ergibt
+// eine Spalte, die Spalten laufen wie der Wellenform-Verlauf von rechts nach links.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use image::ImageBuffer;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use slint::{Image, Rgba8Pixel, SharedPixelBuffer};
+
+use crate::colormap::Colormap;
+use crate::windowing::{self, WindowKind};
+
+/// Untergrenze der Farbskala in dBFS
+const FLOOR_DB: f32 = -100.0;
+
+pub struct Spectrogram {
+    fft_size: usize,
+    fft: Arc<dyn Fft<f32>>,
+    window_kind: WindowKind,
+    window: Arc<[f32]>,
+    /// Noch nicht ausgewertete Samples
+    input: Vec<f32>,
+    buffer: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    /// Pegel je Frequenz-Bin in dBFS, die neueste Spalte hinten
+    columns: VecDeque<Vec<f32>>,
+    max_columns: usize,
+}
+
+impl Spectrogram {
+    /// Erstellt ein Spektrogramm mit `fft_size` Punkten, das höchstens `max_columns` Spalten behält.
+    pub fn new(fft_size: usize, max_columns: usize) -> Self {
+        let fft_size = fft_size.max(2);
+        let fft = FftPlanner::new().plan_fft_forward(fft_size);
+        let scratch = vec![Complex::default(); fft.get_inplace_scratch_len()];
+        let window_kind = WindowKind::default();
+        Spectrogram {
+            fft_size,
+            fft,
+            window_kind,
+            window: windowing::window(window_kind, fft_size),
+            input: Vec::with_capacity(fft_size * 2),
+            buffer: vec![Complex::default(); fft_size],
+            scratch,
+            columns: VecDeque::with_capacity(max_columns),
+            max_columns,
+        }
+    }
+
+    /// Ändert die FFT-Größe. Der bisherige Verlauf passt nicht mehr und wird verworfen.
+    pub fn set_fft_size(&mut self, fft_size: usize) {
+        if fft_size != self.fft_size {
+            let window_kind = self.window_kind;
+            *self = Spectrogram::new(fft_size, self.max_columns);
+            self.set_window(window_kind);
+        }
+    }
+
+    /// Wählt das Analysefenster für die folgenden FFTs, der bisherige Verlauf bleibt.
+    pub fn set_window(&mut self, kind: WindowKind) {
+        if kind != self.window_kind {
+            self.window_kind = kind;
+            self.window = windowing::window(kind, self.fft_size);
+        }
+    }
+
+    /// Hängt neue Samples an und berechnet für jedes volle Fenster eine Spalte.
+    /// Aufeinanderfolgende Fenster überlappen sich um die Hälfte.
+    pub fn push_samples(&mut self, samples: &[f32]) {
+        self.input.extend_from_slice(samples);
+        let hop = self.fft_size / 2;
+        let mut offset = 0;
+        while self.input.len() - offset >= self.fft_size {
+            for (i, value) in self.buffer.iter_mut().enumerate() {
+                *value = Complex::new(self.input[offset + i] * self.window[i], 0.0);
+            }
+            self.fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
+
+            // Amplitude relativ zu einem Vollaussteuerungs-Sinus: Faktor 2 für das einseitige
+            // Spektrum, geteilt durch die Summe des Fensters
+            let norm = 2.0 / self.window.iter().sum::<f32>();
+            let column = self.buffer[..self.fft_size / 2]
+                .iter()
+                .map(|bin| 20.0 * (bin.norm() * norm).max(1e-10).log10())
+                .collect();
+            if self.columns.len() >= self.max_columns {
+                self.columns.pop_front();
+            }
+            self.columns.push_back(column);
+            offset += hop;
+        }
+        self.input.drain(..offset);
+    }
+
+    /// Rendert die Spalten rechtsbündig in den Farben von `colormap`, tiefe Frequenzen unten.
+    pub fn render(&self, width: u32, height: u32, colormap: Colormap) -> Image {
+        let mut img = ImageBuffer::from_pixel(width, height, colormap.map(0.0));
+        let bins = self.fft_size / 2;
+        let first_x = width as i64 - self.columns.len() as i64;
+        for (i, column) in self.columns.iter().enumerate() {
+            let x = first_x + i as i64;
+            if x < 0 {
+                continue;
+            }
+            for y in 0..height {
+                let bin = ((height - 1 - y) as usize * bins) / height as usize;
+                let level = ((column[bin] - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0);
+                img.put_pixel(x as u32, y, colormap.map(level));
+            }
+        }
+
+        let buffer = SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(img.as_raw(), width, height);
+        Image::from_rgba8_premultiplied(buffer)
+    }
+}
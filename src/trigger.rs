@@ -0,0 +1,96 @@
+// Oszilloskop-Ansicht mit Trigger: Aus der Mono-Summe der Eingangs-Samples wird ein Ausschnitt
+// ab einem Pegeldurchgang gezeigt, damit periodische Signale stillstehen.
+
+use std::collections::VecDeque;
+
+/// Flanke, auf die getriggert wird.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Rising,
+    Falling,
+}
+
+/// Verhalten ohne Trigger-Ereignis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerMode {
+    /// Zeigt ungetriggert die neuesten Samples (frei laufend)
+    Auto,
+    /// Hält das letzte getriggerte Bild, bis wieder ein Trigger kommt
+    Normal,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TriggerSettings {
+    /// Schwelle in ±1.0 bei Vollausschlag
+    pub level: f32,
+    pub edge: Edge,
+    pub mode: TriggerMode,
+}
+
+/// Index des letzten Samples in `samples`, an dem das Signal `level` in Richtung `edge`
+/// durchläuft und ab dem noch mindestens `len` Samples folgen.
+pub fn find_trigger(samples: &[f32], len: usize, level: f32, edge: Edge) -> Option<usize> {
+    let last = samples.len().checked_sub(len)?;
+    (1..=last).rev().find(|&i| match edge {
+        Edge::Rising => samples[i - 1] < level && samples[i] >= level,
+        Edge::Falling => samples[i - 1] > level && samples[i] <= level,
+    })
+}
+
+pub struct Scope {
+    /// Neueste Samples, höchstens `capacity`
+    input: VecDeque<f32>,
+    capacity: usize,
+    /// Zuletzt gezeigter Ausschnitt
+    frame: Vec<f32>,
+}
+
+impl Scope {
+    /// Erstellt eine Ansicht, die höchstens `capacity` Samples für die Trigger-Suche behält.
+    pub fn new(capacity: usize) -> Self {
+        Scope { input: VecDeque::with_capacity(capacity), capacity, frame: Vec::new() }
+    }
+
+    /// Hängt neue Samples an, ältere fallen bei voller Kapazität heraus.
+    pub fn push_samples(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            if self.input.len() >= self.capacity {
+                self.input.pop_front();
+            }
+            self.input.push_back(sample);
+        }
+    }
+
+    /// Sucht den letzten Trigger, nach dem noch `len` Samples vorliegen, und übernimmt den
+    /// Ausschnitt ab dort. Samples vor dem Trigger werden verworfen, damit ein Durchgang nur
+    /// einmal zählt. Liefert `true`, wenn getriggert wurde.
+    pub fn update(&mut self, len: usize, settings: TriggerSettings) -> bool {
+        let len = len.min(self.capacity);
+        let samples = self.input.make_contiguous();
+        match find_trigger(samples, len, settings.level, settings.edge) {
+            Some(start) => {
+                self.frame.clear();
+                self.frame.extend_from_slice(&samples[start..start + len]);
+                self.input.drain(..start);
+                true
+            }
+            None => {
+                if settings.mode == TriggerMode::Auto && samples.len() >= len {
+                    self.frame.clear();
+                    self.frame.extend_from_slice(&samples[samples.len() - len..]);
+                }
+                false
+            }
+        }
+    }
+
+    /// Verwirft alle Samples und das letzte Bild.
+    pub fn reset(&mut self) {
+        self.input.clear();
+        self.frame.clear();
+    }
+
+    pub fn frame(&self) -> &[f32] {
+        &self.frame
+    }
+}
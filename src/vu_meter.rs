@@ -0,0 +1,70 @@
+// Pegelbalken mit Ballistik: das Quadrat der Pegel wird mit einer Zeitkonstante integriert, wie
+// bei Schallpegelmessern mit den Zeitbewertungen "Fast" und "Slow".
+
+use std::time::Duration;
+
+/// Zeitkonstante der Zeitbewertung "Fast"
+pub const FAST: Duration = Duration::from_millis(125);
+/// Zeitkonstante der Zeitbewertung "Slow"
+pub const SLOW: Duration = Duration::from_secs(1);
+
+/// Integrierter Pegel der beiden angezeigten Kanäle
+#[derive(Default)]
+pub struct VuMeter {
+    mean_square: (f32, f32),
+}
+
+impl VuMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Integriert einen Punkt je Kanal, der die Dauer `step` abdeckt. Über die Dauer statt je
+    /// Frame, damit die Ballistik nicht von Bildrate und Chunk-Größe abhängt.
+    pub fn push(&mut self, (left, right): (f32, f32), step: Duration, integration: Duration) {
+        let weight = if integration.is_zero() { 1.0 } else { 1.0 - (-step.as_secs_f32() / integration.as_secs_f32()).exp() };
+        self.mean_square.0 += (left * left - self.mean_square.0) * weight;
+        self.mean_square.1 += (right * right - self.mean_square.1) * weight;
+    }
+
+    /// Angezeigter Pegel je Kanal in dBFS, nicht unter `floor_db`.
+    pub fn levels_db(&self, floor_db: f32) -> (f32, f32) {
+        let to_db = |mean_square: f32| (10.0 * mean_square.log10()).max(floor_db);
+        (to_db(self.mean_square.0), to_db(self.mean_square.1))
+    }
+
+    pub fn reset(&mut self) {
+        self.mean_square = (0.0, 0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MIN_LEVEL_DB;
+
+    #[test]
+    fn vu_meter_follows_its_integration_time() {
+        let step = Duration::from_millis(10);
+        let mut fast = VuMeter::new();
+        let mut slow = VuMeter::new();
+        // Nach einer Zeitkonstante erreicht das Quadrat 1 - 1/e des Endwerts
+        for _ in 0..12 {
+            fast.push((0.5, 0.0), step, FAST);
+        }
+        for _ in 0..100 {
+            slow.push((0.5, 0.0), step, SLOW);
+        }
+        let expected = 10.0 * (0.25 * (1.0 - (-1.0f32).exp())).log10();
+        assert!((fast.levels_db(MIN_LEVEL_DB).0 - expected).abs() < 0.5, "{:?}", fast.levels_db(MIN_LEVEL_DB));
+        assert!((slow.levels_db(MIN_LEVEL_DB).0 - expected).abs() < 0.1, "{:?}", slow.levels_db(MIN_LEVEL_DB));
+        // Ein stummer Kanal bleibt an der Untergrenze
+        assert_eq!(fast.levels_db(MIN_LEVEL_DB).1, MIN_LEVEL_DB);
+        // Eingeschwungen entspricht der Pegel dem Eingang
+        for _ in 0..1000 {
+            fast.push((0.5, 0.5), step, FAST);
+        }
+        let (left, right) = fast.levels_db(MIN_LEVEL_DB);
+        assert!((left - 20.0 * 0.5f32.log10()).abs() < 0.01 && (right - left).abs() < 0.01);
+    }
+}
@@ -0,0 +1,85 @@
+// WAV-Datei als Alternative zum Live-Eingang. Die Samples laufen durch dieselbe Reduktion wie im
+// Audio-Thread, damit die Anzeige genauso aussieht.
+
+use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::a_weighting::AWeighting;
+use crate::dc_filter::DcBlocker;
+use crate::params::Params;
+use crate::{aligned_chunk_size, reduce_chunk, StreamInfo, WaveformMode, WaveformPoint};
+
+pub struct WavFile {
+    pub info: StreamInfo,
+    /// Alle Samples interleavt, wie `process_audio` sie nach der Konvertierung sieht
+    samples: Vec<f32>,
+    /// Reduzierte Punkte der ganzen Datei
+    points: Vec<WaveformPoint>,
+    chunk_size: usize,
+    mode: WaveformMode,
+    downmix: bool,
+    dc_cutoff_hz: Option<u32>,
+    a_weighting: bool,
+}
+
+impl WavFile {
+    /// Liest die ganze Datei ein. Ganzzahlige Samples werden wie im Live-Betrieb auf ±1.0
+    /// normiert.
+    pub fn open(path: &Path, params: &Params) -> Result<Self, Box<dyn Error>> {
+        let mut reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+            hound::SampleFormat::Int => {
+                let full_scale = (1u64 << (spec.bits_per_sample.clamp(1, 32) - 1)) as f32;
+                reader.samples::<i32>().map(|s| s.map(|s| s as f32 / full_scale)).collect::<Result<Vec<_>, _>>()?
+            }
+        };
+        let info = StreamInfo { sample_rate: spec.sample_rate, device_sample_rate: spec.sample_rate, requested_sample_rate: None, channels: spec.channels.max(1) as usize, sample_format: None, significant_bits: None, buffer_size: None, requested_buffer_size: None, resampler_latency: Duration::ZERO };
+        let mut file = WavFile { info, samples, points: Vec::new(), chunk_size: 0, mode: params.waveform_mode(), downmix: false, dc_cutoff_hz: None, a_weighting: false };
+        file.update(params);
+        Ok(file)
+    }
+
+    /// Reduziert die Datei neu, falls sich Chunk-Größe, Modus, Downmix, DC-Filter oder
+    /// A-Bewertung in `params` geändert haben.
+    pub fn update(&mut self, params: &Params) {
+        let chunk_size = aligned_chunk_size(params.chunk_size(), self.info.channels);
+        let (mode, downmix, dc_cutoff_hz, a_weighting) = (params.waveform_mode(), params.downmix(), params.dc_cutoff_hz(), params.a_weighting());
+        if chunk_size == self.chunk_size && mode == self.mode && downmix == self.downmix && dc_cutoff_hz == self.dc_cutoff_hz && a_weighting == self.a_weighting {
+            return;
+        }
+        self.chunk_size = chunk_size;
+        self.mode = mode;
+        self.downmix = downmix;
+        self.dc_cutoff_hz = dc_cutoff_hz;
+        self.a_weighting = a_weighting;
+        let filtered;
+        let samples = if dc_cutoff_hz.is_some() || a_weighting {
+            let mut samples = self.samples.clone();
+            if let Some(dc_cutoff_hz) = dc_cutoff_hz {
+                DcBlocker::new().process(&mut samples, self.info.channels, 0, DcBlocker::pole(dc_cutoff_hz as f32, self.info.sample_rate));
+            }
+            if a_weighting {
+                AWeighting::new().process(&mut samples, self.info.channels, 0, self.info.sample_rate);
+            }
+            filtered = samples;
+            &filtered
+        } else {
+            &self.samples
+        };
+        // Ein unvollständiger letzter Chunk wird wie im Live-Betrieb nicht angezeigt
+        let frames_per_chunk = chunk_size / self.info.channels;
+        let sample_rate = self.info.sample_rate.max(1) as f64;
+        self.points = samples
+            .chunks_exact(chunk_size)
+            .enumerate()
+            .map(|(idx, chunk)| WaveformPoint { time: Duration::from_secs_f64((idx * frames_per_chunk) as f64 / sample_rate), ..reduce_chunk(chunk, self.info.channels, mode, downmix) })
+            .collect();
+    }
+
+    pub fn points(&self) -> &[WaveformPoint] {
+        &self.points
+    }
+}
@@ -0,0 +1,79 @@
+// Fensterfunktionen für FFT-Analysen. Die Fenster sind periodisch (Länge N, Periode N), wie für
+// eine FFT über aufeinanderfolgende, überlappende Blöcke üblich. Berechnete Koeffizienten werden
+// je Art und Größe zwischengespeichert, da sich beide nur selten ändern.
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Art des Analysefensters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum WindowKind {
+    /// Guter Kompromiss aus Frequenzauflösung und Leckeffekt
+    #[default]
+    Hann,
+    /// Niedrigere erste Nebenkeule als Hann, dafür fallen weitere Nebenkeulen langsamer ab
+    Hamming,
+    /// Sehr geringer Leckeffekt, breitere Hauptkeule
+    Blackman,
+    /// Ohne Fensterung, beste Frequenzauflösung, starker Leckeffekt
+    Rectangular,
+}
+
+impl WindowKind {
+    /// Fenster für `fft-window` in der UI, unbekannte Werte ergeben Hann.
+    pub fn from_index(index: i32) -> Self {
+        match index {
+            1 => WindowKind::Hamming,
+            2 => WindowKind::Blackman,
+            3 => WindowKind::Rectangular,
+            _ => WindowKind::Hann,
+        }
+    }
+
+    /// Berechnet die `size` Koeffizienten des Fensters.
+    pub fn coefficients(self, size: usize) -> Vec<f32> {
+        let phase = |i: usize| 2.0 * PI * i as f32 / size as f32;
+        (0..size)
+            .map(|i| match self {
+                WindowKind::Hann => 0.5 - 0.5 * phase(i).cos(),
+                WindowKind::Hamming => 0.54 - 0.46 * phase(i).cos(),
+                WindowKind::Blackman => 0.42 - 0.5 * phase(i).cos() + 0.08 * (2.0 * phase(i)).cos(),
+                WindowKind::Rectangular => 1.0,
+            })
+            .collect()
+    }
+}
+
+/// Bereits berechnete Fenster je Art und Größe
+type WindowCache = Mutex<HashMap<(WindowKind, usize), Arc<[f32]>>>;
+
+/// Koeffizienten des Fensters `kind` mit `size` Punkten, beim ersten Aufruf berechnet.
+pub fn window(kind: WindowKind, size: usize) -> Arc<[f32]> {
+    static CACHE: OnceLock<WindowCache> = OnceLock::new();
+    let mut cache = CACHE.get_or_init(Default::default).lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    cache.entry((kind, size)).or_insert_with(|| kind.coefficients(size).into()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analysis_windows_have_the_expected_shape() {
+        let hann = window(WindowKind::Hann, 1024);
+        // Periodisch: 0 am Anfang, 1 in der Mitte, symmetrisch um die Mitte, Summe N/2
+        assert_eq!(hann.len(), 1024);
+        assert!(hann[0].abs() < 1e-6);
+        assert!((hann[512] - 1.0).abs() < 1e-6);
+        assert!((1..512).all(|i| (hann[i] - hann[1024 - i]).abs() < 1e-5));
+        assert!((hann.iter().sum::<f32>() - 512.0).abs() < 1e-2);
+        // Hamming endet bei 0.08, Blackman bei 0, Rechteck ist konstant
+        assert!((WindowKind::Hamming.coefficients(64)[0] - 0.08).abs() < 1e-6);
+        assert!(WindowKind::Blackman.coefficients(64)[0].abs() < 1e-6);
+        assert!(WindowKind::Rectangular.coefficients(64).iter().all(|&c| c == 1.0));
+        // Der Cache liefert dieselben Koeffizienten
+        assert!(std::sync::Arc::ptr_eq(&hann, &window(WindowKind::Hann, 1024)));
+        assert_eq!(WindowKind::from_index(7), WindowKind::Hann);
+    }
+}